@@ -0,0 +1,437 @@
+// Persistent user configuration, read from $XDG_CONFIG_HOME/ugdb/config (or ~/.config/ugdb/config).
+//
+// The file is in toml format. All entries are optional, for example:
+//
+//   gdb = "rust-gdb"
+//   log_dir = "/tmp"
+//   theme = "base16-ocean.dark"
+//   layout = "(2s-c)|(e-b-t)"
+//
+//   [timing]
+//   cursor_blink_period_ms = 500
+//   cursor_blink_times = 20
+//   focus_escape_max_duration_ms = 200
+//
+//   [colors]
+//   normal_border = "default"
+//   focused_border = "red"
+//   container_select_border = "#ffff00"
+//
+//   [keys]
+//   "srcview.toggle_breakpoint" = ["Space", "b"]
+//
+// (See keymap.rs for all available actions. Only single keys can be bound, not key sequences.)
+use keymap::{self, Action, Keymap};
+use layout::{self, LayoutNode};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use toml::value::Table;
+use toml::Value;
+use unsegen::base::{Color, StyleModifier};
+
+const CONFIG_SUBDIR: &str = "ugdb";
+const CONFIG_FILE_NAME: &str = "config";
+
+pub const DEFAULT_THEME: &str = "base16-ocean.dark";
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(PathBuf, io::Error),
+    Syntax(PathBuf, ::toml::de::Error),
+    UnknownKey(String),
+    WrongType(String, &'static str),
+    InvalidValue(String, String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::Io(path, e) => write!(f, "Could not read {}: {}", path.display(), e),
+            ConfigError::Syntax(path, e) => write!(f, "Malformed {}: {}", path.display(), e),
+            ConfigError::UnknownKey(key) => write!(f, "Unknown configuration entry '{}'", key),
+            ConfigError::WrongType(key, expected) => {
+                write!(f, "Configuration entry '{}' has to be {}", key, expected)
+            }
+            ConfigError::InvalidValue(key, reason) => {
+                write!(f, "Invalid value for configuration entry '{}': {}", key, reason)
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TimingConfig {
+    pub cursor_blink_period_ms: u64,
+    pub cursor_blink_times: u8,
+    pub focus_escape_max_duration_ms: u64,
+}
+
+impl Default for TimingConfig {
+    fn default() -> Self {
+        TimingConfig {
+            cursor_blink_period_ms: 500,
+            cursor_blink_times: 20,
+            focus_escape_max_duration_ms: 200,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColorConfig {
+    pub normal_border: Option<Color>,
+    pub focused_border: Option<Color>,
+    pub container_select_border: Option<Color>,
+}
+
+impl Default for ColorConfig {
+    fn default() -> Self {
+        ColorConfig {
+            normal_border: None,
+            focused_border: Some(Color::Red),
+            container_select_border: Some(Color::LightYellow),
+        }
+    }
+}
+
+fn style_with_fg(color: Option<Color>) -> StyleModifier {
+    if let Some(color) = color {
+        StyleModifier::new().fg_color(color)
+    } else {
+        StyleModifier::new()
+    }
+}
+
+impl ColorConfig {
+    pub fn normal_border_style(&self) -> StyleModifier {
+        style_with_fg(self.normal_border)
+    }
+    pub fn focused_border_style(&self) -> StyleModifier {
+        style_with_fg(self.focused_border)
+    }
+    pub fn container_select_border_style(&self) -> StyleModifier {
+        style_with_fg(self.container_select_border)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Config {
+    pub gdb_path: PathBuf,
+    pub log_dir: PathBuf,
+    pub theme: String,
+    pub layout: LayoutNode,
+    pub timing: TimingConfig,
+    pub colors: ColorConfig,
+    pub keymap: Keymap,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            gdb_path: PathBuf::from("gdb"),
+            log_dir: PathBuf::from("/tmp"),
+            theme: DEFAULT_THEME.to_owned(),
+            layout: layout::parse(layout::DEFAULT_LAYOUT).expect("default layout is valid"),
+            timing: TimingConfig::default(),
+            colors: ColorConfig::default(),
+            keymap: Keymap::default(),
+        }
+    }
+}
+
+pub fn default_config_file_path() -> Option<PathBuf> {
+    let config_dir = ::std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| ::std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+    Some(config_dir.join(CONFIG_SUBDIR).join(CONFIG_FILE_NAME))
+}
+
+fn expect_str<'a>(key: &str, value: &'a Value) -> Result<&'a str, ConfigError> {
+    value
+        .as_str()
+        .ok_or_else(|| ConfigError::WrongType(key.to_owned(), "a string"))
+}
+
+fn expect_table<'a>(key: &str, value: &'a Value) -> Result<&'a Table, ConfigError> {
+    value
+        .as_table()
+        .ok_or_else(|| ConfigError::WrongType(key.to_owned(), "a table"))
+}
+
+fn expect_u64(key: &str, value: &Value) -> Result<u64, ConfigError> {
+    value
+        .as_integer()
+        .and_then(|i| if i >= 0 { Some(i as u64) } else { None })
+        .ok_or_else(|| ConfigError::WrongType(key.to_owned(), "a non-negative integer"))
+}
+
+pub fn parse_color(s: &str) -> Result<Option<Color>, String> {
+    let color = match s.to_lowercase().as_str() {
+        "default" | "none" => return Ok(None),
+        "black" => Color::Black,
+        "red" => Color::Red,
+        "green" => Color::Green,
+        "yellow" => Color::Yellow,
+        "blue" => Color::Blue,
+        "magenta" => Color::Magenta,
+        "cyan" => Color::Cyan,
+        "white" => Color::White,
+        "lightblack" => Color::LightBlack,
+        "lightred" => Color::LightRed,
+        "lightgreen" => Color::LightGreen,
+        "lightyellow" => Color::LightYellow,
+        "lightblue" => Color::LightBlue,
+        "lightmagenta" => Color::LightMagenta,
+        "lightcyan" => Color::LightCyan,
+        "lightwhite" => Color::LightWhite,
+        other => {
+            if other.starts_with('#') && other.len() == 7 {
+                let component = |i: usize| {
+                    u8::from_str_radix(&other[i..i + 2], 16)
+                        .map_err(|_| format!("'{}' is not a valid hex color", s))
+                };
+                Color::Rgb {
+                    r: component(1)?,
+                    g: component(3)?,
+                    b: component(5)?,
+                }
+            } else if let Ok(ansi) = other.parse::<u8>() {
+                Color::Ansi(ansi)
+            } else {
+                return Err(format!(
+                    "'{}' is neither a color name, a hex color (#rrggbb), nor an ansi color index",
+                    s
+                ));
+            }
+        }
+    };
+    Ok(Some(color))
+}
+
+impl Config {
+    pub fn load(explicit_path: Option<&Path>) -> Result<Self, ConfigError> {
+        let path = if let Some(path) = explicit_path {
+            path.to_owned()
+        } else if let Some(path) = default_config_file_path() {
+            if !path.exists() {
+                return Ok(Config::default());
+            }
+            path
+        } else {
+            return Ok(Config::default());
+        };
+        let content = fs::read_to_string(&path).map_err(|e| ConfigError::Io(path.clone(), e))?;
+        let value = content
+            .parse::<Value>()
+            .map_err(|e| ConfigError::Syntax(path.clone(), e))?;
+        Self::from_value(&value)
+    }
+
+    fn from_value(value: &Value) -> Result<Self, ConfigError> {
+        let mut config = Config::default();
+        for (key, value) in expect_table("", value)? {
+            match key.as_str() {
+                "gdb" => config.gdb_path = PathBuf::from(expect_str(key, value)?),
+                "log_dir" => config.log_dir = PathBuf::from(expect_str(key, value)?),
+                "theme" => config.theme = expect_str(key, value)?.to_owned(),
+                "layout" => {
+                    config.layout = layout::parse(expect_str(key, value)?)
+                        .map_err(|e| ConfigError::InvalidValue(key.clone(), e.to_string()))?
+                }
+                "timing" => config.timing.apply(expect_table(key, value)?)?,
+                "colors" => config.colors.apply(expect_table(key, value)?)?,
+                "keys" => apply_key_bindings(&mut config.keymap, expect_table(key, value)?)?,
+                _ => return Err(ConfigError::UnknownKey(key.clone())),
+            }
+        }
+        Ok(config)
+    }
+}
+
+impl TimingConfig {
+    fn apply(&mut self, table: &Table) -> Result<(), ConfigError> {
+        for (key, value) in table {
+            let full_key = format!("timing.{}", key);
+            match key.as_str() {
+                "cursor_blink_period_ms" => {
+                    self.cursor_blink_period_ms = expect_u64(&full_key, value)?
+                }
+                "cursor_blink_times" => {
+                    let times = expect_u64(&full_key, value)?;
+                    if times > u8::max_value() as u64 {
+                        return Err(ConfigError::InvalidValue(
+                            full_key,
+                            format!("at most {} blinks are supported", u8::max_value()),
+                        ));
+                    }
+                    self.cursor_blink_times = times as u8;
+                }
+                "focus_escape_max_duration_ms" => {
+                    self.focus_escape_max_duration_ms = expect_u64(&full_key, value)?
+                }
+                _ => return Err(ConfigError::UnknownKey(full_key)),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ColorConfig {
+    fn apply(&mut self, table: &Table) -> Result<(), ConfigError> {
+        for (key, value) in table {
+            let full_key = format!("colors.{}", key);
+            let color = parse_color(expect_str(&full_key, value)?)
+                .map_err(|e| ConfigError::InvalidValue(full_key.clone(), e))?;
+            match key.as_str() {
+                "normal_border" => self.normal_border = color,
+                "focused_border" => self.focused_border = color,
+                "container_select_border" => self.container_select_border = color,
+                _ => return Err(ConfigError::UnknownKey(full_key)),
+            }
+        }
+        Ok(())
+    }
+}
+
+fn apply_key_bindings(keymap: &mut Keymap, table: &Table) -> Result<(), ConfigError> {
+    for (key, value) in table {
+        let full_key = format!("keys.{}", key);
+        let action =
+            Action::from_name(key).ok_or_else(|| ConfigError::UnknownKey(full_key.clone()))?;
+        let key_names = match value {
+            Value::String(s) => vec![s.as_str()],
+            Value::Array(a) => a
+                .iter()
+                .map(|v| expect_str(&full_key, v))
+                .collect::<Result<Vec<_>, _>>()?,
+            _ => {
+                return Err(ConfigError::WrongType(
+                    full_key,
+                    "a key or a list of keys",
+                ))
+            }
+        };
+        let keys = key_names
+            .into_iter()
+            .map(keymap::parse_key)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| ConfigError::InvalidValue(full_key.clone(), e))?;
+        keymap.bind(action, keys);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use unsegen::input::Key;
+
+    fn parse(s: &str) -> Result<Config, ConfigError> {
+        Config::from_value(&s.parse::<Value>().unwrap())
+    }
+
+    #[test]
+    fn test_empty_config() {
+        assert_eq!(parse("").unwrap(), Config::default());
+    }
+
+    #[test]
+    fn test_full_config() {
+        let config = parse(
+            r##"
+            gdb = "rust-gdb"
+            log_dir = "/var/log"
+            theme = "Solarized (dark)"
+            layout = "s|c"
+
+            [timing]
+            cursor_blink_period_ms = 300
+            cursor_blink_times = 5
+            focus_escape_max_duration_ms = 100
+
+            [colors]
+            normal_border = "blue"
+            focused_border = "#ff8000"
+            container_select_border = "default"
+
+            [keys]
+            "srcview.toggle_breakpoint" = ["Space", "b"]
+            "container.enter_select_mode" = "C-a"
+            "##,
+        )
+        .unwrap();
+        assert_eq!(config.gdb_path, PathBuf::from("rust-gdb"));
+        assert_eq!(config.log_dir, PathBuf::from("/var/log"));
+        assert_eq!(config.theme, "Solarized (dark)");
+        assert_eq!(config.layout, layout::parse("s|c").unwrap());
+        assert_eq!(
+            config.timing,
+            TimingConfig {
+                cursor_blink_period_ms: 300,
+                cursor_blink_times: 5,
+                focus_escape_max_duration_ms: 100,
+            }
+        );
+        assert_eq!(
+            config.colors,
+            ColorConfig {
+                normal_border: Some(Color::Blue),
+                focused_border: Some(Color::Rgb {
+                    r: 0xff,
+                    g: 0x80,
+                    b: 0x00
+                }),
+                container_select_border: None,
+            }
+        );
+        assert_eq!(
+            config.keymap.keys(Action::SrcViewToggleBreakpoint),
+            &[Key::Char(' '), Key::Char('b')]
+        );
+        assert_eq!(
+            config.keymap.keys(Action::EnterContainerSelect),
+            &[Key::Ctrl('a')]
+        );
+        assert_eq!(
+            config.keymap.keys(Action::SrcViewScrollDown),
+            Keymap::default().keys(Action::SrcViewScrollDown)
+        );
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        match parse("foo = 1") {
+            Err(ConfigError::UnknownKey(k)) => assert_eq!(k, "foo"),
+            _ => panic!("expected unknown key error"),
+        }
+        match parse("[timing]\nblink = 1") {
+            Err(ConfigError::UnknownKey(k)) => assert_eq!(k, "timing.blink"),
+            _ => panic!("expected unknown key error"),
+        }
+        match parse("theme = 1") {
+            Err(ConfigError::WrongType(k, _)) => assert_eq!(k, "theme"),
+            _ => panic!("expected type error"),
+        }
+        match parse("[timing]\ncursor_blink_times = 1000") {
+            Err(ConfigError::InvalidValue(k, _)) => assert_eq!(k, "timing.cursor_blink_times"),
+            _ => panic!("expected invalid value error"),
+        }
+        match parse("[colors]\nfocused_border = \"reddish\"") {
+            Err(ConfigError::InvalidValue(k, _)) => assert_eq!(k, "colors.focused_border"),
+            _ => panic!("expected invalid value error"),
+        }
+        match parse("[keys]\n\"srcview.fly\" = \"f\"") {
+            Err(ConfigError::UnknownKey(k)) => assert_eq!(k, "keys.srcview.fly"),
+            _ => panic!("expected unknown key error"),
+        }
+        match parse("[keys]\n\"srcview.toggle_breakpoint\" = \"Spacebar\"") {
+            Err(ConfigError::InvalidValue(k, _)) => assert_eq!(k, "keys.srcview.toggle_breakpoint"),
+            _ => panic!("expected invalid value error"),
+        }
+        match parse("layout = \"s|x\"") {
+            Err(ConfigError::InvalidValue(k, _)) => assert_eq!(k, "layout"),
+            _ => panic!("expected invalid value error"),
+        }
+    }
+}
@@ -0,0 +1,2112 @@
+// This module encapsulates some functionality of gdb. Depending on how general this turns out, we
+// may want to move it to a separate crate or merge it with gdbmi-rs
+
+mod signals;
+
+use self::signals::{is_fault_signal, signal_description};
+use gdbmi;
+use gdbmi::commands::{
+    BreakPointLocation, BreakPointNumber, MiCommand, RegisterFormat, ValuePrintMode, WatchMode,
+};
+use gdbmi::output::{
+    BreakPointEvent, JsonValue, LibraryEvent, Object, ResultClass, ResultRecord, StopEvent,
+    StopReason, ThreadEvent,
+};
+use gdbmi::{ExecuteError, Token};
+use log::warn;
+use std::borrow::Borrow;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::ops::{Add, Sub};
+use std::path::PathBuf;
+use unsegen::base::LineNumber;
+
+#[derive(Debug, Clone)]
+pub struct SrcPosition {
+    pub file: PathBuf,
+    pub line: LineNumber,
+}
+
+impl SrcPosition {
+    pub fn new(file: PathBuf, line: LineNumber) -> Self {
+        SrcPosition {
+            file: file,
+            line: line,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Address(pub usize);
+impl Address {
+    pub fn parse(string: &str) -> Result<Self, (::std::num::ParseIntError, String)> {
+        usize::from_str_radix(string.trim_start_matches("0x"), 16)
+            .map(|u| Address(u))
+            .map_err(|e| (e, string.to_owned()))
+    }
+}
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "0x{:x}", self.0)
+    }
+}
+impl Add<usize> for Address {
+    type Output = Self;
+    fn add(self, rhs: usize) -> Self {
+        Address(self.0 + rhs)
+    }
+}
+
+impl Sub<usize> for Address {
+    type Output = Self;
+    fn sub(self, rhs: usize) -> Self {
+        Address(self.0 - rhs)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BreakPointKind {
+    Breakpoint,
+    Watchpoint(WatchMode),
+}
+
+impl BreakPointKind {
+    fn from_type(bp_type: Option<&str>) -> Self {
+        match bp_type {
+            Some("watchpoint") | Some("hw watchpoint") => {
+                BreakPointKind::Watchpoint(WatchMode::Write)
+            }
+            Some("read watchpoint") => BreakPointKind::Watchpoint(WatchMode::Read),
+            Some("acc watchpoint") => BreakPointKind::Watchpoint(WatchMode::Access),
+            _ => BreakPointKind::Breakpoint,
+        }
+    }
+
+    // Names of the corresponding gdb commands
+    pub fn description(self) -> &'static str {
+        match self {
+            BreakPointKind::Breakpoint => "break",
+            BreakPointKind::Watchpoint(WatchMode::Write) => "watch",
+            BreakPointKind::Watchpoint(WatchMode::Read) => "rwatch",
+            BreakPointKind::Watchpoint(WatchMode::Access) => "awatch",
+        }
+    }
+}
+
+pub struct BreakPoint {
+    pub number: BreakPointNumber,
+    pub kind: BreakPointKind,
+    pub expression: Option<String>, // Watched expression of watchpoints
+    pub address: Option<Address>,
+    pub enabled: bool,
+    pub src_pos: Option<SrcPosition>, // May not be present if debug information is missing!
+    pub function: Option<String>,
+    pub original_location: Option<String>, // Location as specified by the user
+    pub condition: Option<String>,
+    pub ignore_count: usize,
+    pub hit_count: usize,
+    pub commands: Vec<String>,
+    pub temporary: bool, // Deleted when hit, e.g., breakpoints inserted by jump
+}
+
+impl BreakPoint {
+    pub fn from_json(bkpt: &Object) -> Result<Self, response::GDBResponseError> {
+        let number = response::get_breakpoint_number_obj(bkpt, "number")?;
+        let enabled = response::get_str_obj(bkpt, "enabled")? == "y";
+        let address = bkpt["addr"]
+            .as_str()
+            .and_then(|addr| Address::parse(addr).ok()); //addr may not be present or contain
+        let src_pos = {
+            let maybe_file = bkpt["fullname"].as_str();
+            let maybe_line = match bkpt["line"].as_str() {
+                Some(l_nr) => Some(LineNumber::new(l_nr.parse::<usize>().map_err(|_| {
+                    response::GDBResponseError::Other(format!("Malformed line number: {}", l_nr))
+                })?)),
+                None => None,
+            };
+            if let (Some(file), Some(line)) = (maybe_file, maybe_line) {
+                Some(SrcPosition::new(PathBuf::from(file), line))
+            } else {
+                None
+            }
+        };
+        let kind = BreakPointKind::from_type(bkpt["type"].as_str());
+        let expression = match kind {
+            BreakPointKind::Watchpoint(_) => bkpt["what"].as_str().map(|s| s.to_owned()),
+            BreakPointKind::Breakpoint => None,
+        };
+        let function = bkpt["func"].as_str().map(|s| s.to_owned());
+        let original_location = bkpt["original-location"].as_str().map(|s| s.to_owned());
+        let condition = bkpt["cond"].as_str().map(|s| s.to_owned());
+        let ignore_count = bkpt["ignore"]
+            .as_str()
+            .and_then(|n| n.parse::<usize>().ok())
+            .unwrap_or(0);
+        let hit_count = bkpt["times"]
+            .as_str()
+            .and_then(|n| n.parse::<usize>().ok())
+            .unwrap_or(0);
+        let commands = bkpt["script"]
+            .members()
+            .filter_map(|c| c.as_str().map(|s| s.to_owned()))
+            .collect();
+        let temporary = bkpt["disp"].as_str() == Some("del");
+        Ok(BreakPoint {
+            number: number,
+            kind: kind,
+            expression: expression,
+            address: address,
+            enabled: enabled,
+            src_pos: src_pos,
+            function: function,
+            original_location: original_location,
+            condition: condition,
+            ignore_count: ignore_count,
+            hit_count: hit_count,
+            commands: commands,
+            temporary: temporary,
+        })
+    }
+
+    // Parse a breakpoint and its locations. Up to gdb 12, the locations (numbered
+    // "<major>.<minor>") follow the breakpoint as a list of tuples. Since gdb 13, they are nested
+    // in the "locations" field of the breakpoint.
+    pub fn all_from_json(
+        bkpt_obj: &JsonValue,
+    ) -> Result<Vec<BreakPoint>, response::GDBResponseError> {
+        match bkpt_obj {
+            &JsonValue::Object(ref bp) => {
+                let mut bps = vec![Self::from_json(&bp)?];
+                for location in bp["locations"].members() {
+                    bps.extend(Self::all_from_json(location)?);
+                }
+                Ok(bps)
+            }
+            &JsonValue::Array(ref bp_array) => {
+                let mut bps = Vec::new();
+                for bp in bp_array {
+                    if !bp.is_object() {
+                        return Err(response::GDBResponseError::Other(format!(
+                            "Malformed breakpoint description: {}",
+                            bp.dump()
+                        )));
+                    }
+                    bps.extend(Self::all_from_json(bp)?);
+                }
+                Ok(bps)
+            }
+            other => Err(response::GDBResponseError::Other(format!(
+                "Malformed breakpoint description: {}",
+                other.dump()
+            ))),
+        }
+    }
+}
+
+pub struct StackFrame {
+    pub level: u64,
+    pub address: Option<Address>,
+    pub function: Option<String>,
+    pub src_pos: Option<SrcPosition>,
+    pub library: Option<String>,
+    pub arguments: Vec<(String, Option<String>)>, // Values are omitted for aggregate types
+}
+
+impl StackFrame {
+    pub fn from_json(frame: &JsonValue) -> Result<Self, response::GDBResponseError> {
+        let level = response::get_u64(frame, "level")?;
+        let address = frame["addr"]
+            .as_str()
+            .and_then(|addr| Address::parse(addr).ok());
+        let src_pos = {
+            let maybe_file = frame["fullname"].as_str();
+            let maybe_line = frame["line"]
+                .as_str()
+                .and_then(|l_nr| l_nr.parse::<usize>().ok())
+                .map(LineNumber::new);
+            if let (Some(file), Some(line)) = (maybe_file, maybe_line) {
+                Some(SrcPosition::new(PathBuf::from(file), line))
+            } else {
+                None
+            }
+        };
+        Ok(StackFrame {
+            level: level,
+            address: address,
+            function: frame["func"].as_str().map(|s| s.to_owned()),
+            src_pos: src_pos,
+            library: frame["from"].as_str().map(|s| s.to_owned()),
+            arguments: Vec::new(),
+        })
+    }
+}
+
+pub struct Variable {
+    pub name: String,
+    pub is_argument: bool,
+    pub value: Option<String>,
+}
+
+// Describe a stop caused by a watchpoint similar to the gdb cli, e.g.:
+//
+//   Watchpoint 2: counter
+//   Old value = 1
+//   New value = 2
+pub fn watchpoint_stop_message(stop: &Object) -> Option<String> {
+    let (kind, watchpoint) = match stop["reason"].as_str() {
+        Some("watchpoint-trigger") => ("Watchpoint", &stop["wpt"]),
+        Some("read-watchpoint-trigger") => ("Read watchpoint", &stop["hw-rwpt"]),
+        Some("access-watchpoint-trigger") => ("Access watchpoint", &stop["hw-awpt"]),
+        Some("watchpoint-scope") => {
+            return Some(format!(
+                "Watchpoint {} deleted because the program has left the block in which its \
+                 expression is valid.",
+                stop["wpnum"].as_str().unwrap_or("?")
+            ));
+        }
+        _ => return None,
+    };
+    let mut message = format!(
+        "{} {}: {}",
+        kind,
+        watchpoint["number"].as_str().unwrap_or("?"),
+        watchpoint["exp"].as_str().unwrap_or("?")
+    );
+    let value = &stop["value"];
+    match (value["old"].as_str(), value["new"].as_str()) {
+        (Some(old), Some(new)) => {
+            message.push_str(&format!("\nOld value = {}\nNew value = {}", old, new))
+        }
+        (None, Some(new)) => message.push_str(&format!("\nNew value = {}", new)),
+        _ => {
+            if let Some(value) = value["value"].as_str() {
+                message.push_str(&format!("\nValue = {}", value));
+            }
+        }
+    }
+    Some(message)
+}
+
+pub struct Register {
+    pub number: usize,
+    pub name: String,
+    pub value: String,
+}
+
+pub struct BreakPointSet {
+    map: HashMap<BreakPointNumber, BreakPoint>,
+    pub last_change: ::std::time::Instant,
+}
+
+impl BreakPointSet {
+    pub fn new() -> Self {
+        BreakPointSet {
+            map: HashMap::new(),
+            last_change: ::std::time::Instant::now(),
+        }
+    }
+
+    fn notify_change(&mut self) {
+        self.last_change = ::std::time::Instant::now();
+    }
+
+    pub fn update_breakpoint(&mut self, new_bp: BreakPoint) {
+        let _ = self.map.insert(new_bp.number, new_bp);
+        //debug_assert!(res.is_some(), "Modified non-existent breakpoint");
+        self.notify_change();
+    }
+
+    pub fn remove_breakpoint(&mut self, bp_num: BreakPointNumber) {
+        self.map.remove(&bp_num);
+        if bp_num.minor.is_none() {
+            self.map.retain(|k, _| k.major != bp_num.major);
+        }
+        self.notify_change();
+    }
+
+    // Breakpoints and breakpoint locations at which the program would actually stop, i.e., that
+    // are enabled themselves and whose breakpoint (in case of a location) is enabled as well.
+    pub fn active_locations<'a>(&'a self) -> Box<Iterator<Item = &'a BreakPoint> + 'a> {
+        Box::new(self.map.values().filter(move |bp| {
+            let major_enabled = match bp.number.minor {
+                Some(_) => self
+                    .map
+                    .get(&BreakPointNumber {
+                        major: bp.number.major,
+                        minor: None,
+                    })
+                    .map(|major| major.enabled)
+                    .unwrap_or(true),
+                None => true,
+            };
+            bp.enabled && major_enabled
+        }))
+    }
+}
+
+impl ::std::ops::Deref for BreakPointSet {
+    type Target = HashMap<BreakPointNumber, BreakPoint>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.map
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ThreadState {
+    Running,
+    Stopped,
+}
+
+pub struct Thread {
+    pub id: u64,
+    pub target_id: Option<String>,
+    pub name: Option<String>,
+    pub state: ThreadState,
+    pub frame: Option<StackFrame>, // Only available for stopped threads
+    pub core: Option<u64>,
+}
+
+impl Thread {
+    pub fn from_json(thread: &JsonValue) -> Result<Self, response::GDBResponseError> {
+        let id = response::get_u64(thread, "id")?;
+        let state = match thread["state"].as_str() {
+            Some("running") => ThreadState::Running,
+            _ => ThreadState::Stopped,
+        };
+        let frame = if thread["frame"].is_object() {
+            Some(StackFrame::from_json(&thread["frame"])?)
+        } else {
+            None
+        };
+        Ok(Thread {
+            id: id,
+            target_id: thread["target-id"].as_str().map(|s| s.to_owned()),
+            name: thread["name"].as_str().map(|s| s.to_owned()),
+            state: state,
+            frame: frame,
+            core: thread["core"].as_str().and_then(|c| c.parse::<u64>().ok()),
+        })
+    }
+}
+
+pub struct ThreadSet {
+    map: BTreeMap<u64, Thread>,
+    pub current: Option<u64>,
+    pub last_change: ::std::time::Instant,
+}
+
+impl ThreadSet {
+    pub fn new() -> Self {
+        ThreadSet {
+            map: BTreeMap::new(),
+            current: None,
+            last_change: ::std::time::Instant::now(),
+        }
+    }
+
+    fn notify_change(&mut self) {
+        self.last_change = ::std::time::Instant::now();
+    }
+
+    fn replace_all(&mut self, threads: Vec<Thread>, current: Option<u64>) {
+        self.map = threads.into_iter().map(|t| (t.id, t)).collect();
+        self.current = current;
+        self.notify_change();
+    }
+
+    // Select the thread without reloading the thread list (see GDB::refresh_threads).
+    pub fn set_current(&mut self, id: u64) {
+        self.current = Some(id);
+        self.notify_change();
+    }
+
+    fn set_state(&mut self, id: Option<u64>, state: ThreadState) {
+        for (_, thread) in self.map.iter_mut() {
+            if id.map(|id| id == thread.id).unwrap_or(true) {
+                thread.state = state;
+                if state == ThreadState::Running {
+                    thread.frame = None;
+                }
+            }
+        }
+        self.notify_change();
+    }
+}
+
+impl ::std::ops::Deref for ThreadSet {
+    type Target = BTreeMap<u64, Thread>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.map
+    }
+}
+
+pub struct Library {
+    pub id: String,
+    pub target_name: String,
+    pub host_name: String,
+    pub symbols_loaded: bool,
+    pub thread_group: Option<String>,
+    pub ranges: Vec<(Address, Address)>,
+}
+
+impl Library {
+    pub fn from_json(library: &JsonValue) -> Result<Self, response::GDBResponseError> {
+        let id = response::get_str(library, "id")?.to_owned();
+        let target_name = library["target-name"]
+            .as_str()
+            .map(|s| s.to_owned())
+            .unwrap_or_else(|| id.clone());
+        let host_name = library["host-name"]
+            .as_str()
+            .map(|s| s.to_owned())
+            .unwrap_or_else(|| target_name.clone());
+        // Older versions of gdb only report a single range as low-address/high-address.
+        let ranges = if library["ranges"].is_array() {
+            library["ranges"]
+                .members()
+                .map(|range| {
+                    Ok((
+                        response::get_addr(range, "from")?,
+                        response::get_addr(range, "to")?,
+                    ))
+                })
+                .collect::<Result<Vec<_>, response::GDBResponseError>>()?
+        } else if library["low-address"].is_string() {
+            vec![(
+                response::get_addr(library, "low-address")?,
+                response::get_addr(library, "high-address")?,
+            )]
+        } else {
+            Vec::new()
+        };
+        Ok(Library {
+            id: id,
+            target_name: target_name,
+            host_name: host_name,
+            symbols_loaded: library["symbols-loaded"].as_str() == Some("1"),
+            thread_group: library["thread-group"].as_str().map(|s| s.to_owned()),
+            ranges: ranges,
+        })
+    }
+
+    pub fn contains(&self, address: Address) -> bool {
+        self.ranges
+            .iter()
+            .any(|&(from, to)| from <= address && address < to)
+    }
+}
+
+// The shared libraries loaded by the program in the order in which they were loaded
+pub struct LibrarySet {
+    libraries: Vec<Library>,
+    pub last_change: ::std::time::Instant,
+}
+
+impl LibrarySet {
+    pub fn new() -> Self {
+        LibrarySet {
+            libraries: Vec::new(),
+            last_change: ::std::time::Instant::now(),
+        }
+    }
+
+    fn notify_change(&mut self) {
+        self.last_change = ::std::time::Instant::now();
+    }
+
+    fn update_library(&mut self, library: Library) {
+        if let Some(old) = self.libraries.iter_mut().find(|l| l.id == library.id) {
+            *old = library;
+        } else {
+            self.libraries.push(library);
+        }
+        self.notify_change();
+    }
+
+    fn remove_library(&mut self, id: &str) {
+        self.libraries.retain(|l| l.id != id);
+        self.notify_change();
+    }
+
+    fn remove_thread_group(&mut self, thread_group: &str) {
+        self.libraries
+            .retain(|l| l.thread_group.as_ref().map(|g| g.as_str()) != Some(thread_group));
+        self.notify_change();
+    }
+
+    fn replace_all(&mut self, libraries: Vec<Library>) {
+        self.libraries = libraries;
+        self.notify_change();
+    }
+}
+
+impl ::std::ops::Deref for LibrarySet {
+    type Target = Vec<Library>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.libraries
+    }
+}
+
+// Regular expression (as understood by the sharedlibrary command) that matches exactly the given
+// library name.
+fn library_name_regex(name: &str) -> String {
+    let mut regex = String::from("^");
+    for c in name.chars() {
+        if "\\.+*?()|[]{}^$".contains(c) {
+            regex.push('\\');
+        }
+        regex.push(c);
+    }
+    regex.push('$');
+    regex
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InferiorState {
+    NotStarted,
+    Running,
+    Stopped,
+    Exited(Option<u32>), // Exit code (if the program was not terminated by a signal)
+}
+
+impl fmt::Display for InferiorState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InferiorState::NotStarted => write!(f, "not started"),
+            InferiorState::Running => write!(f, "running"),
+            InferiorState::Stopped => write!(f, "stopped"),
+            InferiorState::Exited(Some(code)) => write!(f, "exited with code {}", code),
+            InferiorState::Exited(None) => write!(f, "terminated"),
+        }
+    }
+}
+
+// Connection to gdbserver or another remote stub
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RemoteTarget {
+    pub address: String,
+    pub extended: bool,
+    pub connected: bool,
+}
+
+impl fmt::Display for RemoteTarget {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} ({})",
+            if self.extended {
+                "extended-remote"
+            } else {
+                "remote"
+            },
+            self.address,
+            if self.connected {
+                "connected"
+            } else {
+                "disconnected"
+            }
+        )
+    }
+}
+
+// gdb does not send a notification if the connection to a remote target is lost, it only
+// informs the user on the console.
+pub fn is_remote_disconnect_message(console_output: &str) -> bool {
+    let message = console_output.trim_start();
+    message.starts_with("Remote connection closed")
+        || message.starts_with("Remote communication error")
+        || message.starts_with("Ending remote debugging")
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExecutionDirection {
+    Forward,
+    Reverse,
+}
+
+// Post-mortem information about a program that has been loaded from a core dump.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CoreDump {
+    pub signal: Option<u32>,
+    pub fault_address: Option<Address>,
+}
+
+impl CoreDump {
+    pub fn signal_name(&self) -> Option<String> {
+        self.signal.map(|signal| match signal_description(signal) {
+            Some((name, _)) => name.to_owned(),
+            None => format!("signal {}", signal),
+        })
+    }
+
+    pub fn description(&self) -> String {
+        let mut description = match self.signal {
+            Some(signal) => match signal_description(signal) {
+                Some((name, meaning)) => {
+                    format!("Program terminated with signal {}, {}.", name, meaning)
+                }
+                None => format!("Program terminated with signal {}.", signal),
+            },
+            None => "Program terminated (unknown signal).".to_owned(),
+        };
+        if let Some(address) = self.fault_address {
+            description += &format!(" Fault address: {}.", address);
+        }
+        description
+    }
+}
+
+// How long to wait for the program to stop after interrupting it in order to execute a command.
+const INTERRUPT_TIMEOUT: ::std::time::Duration = ::std::time::Duration::from_secs(2);
+
+fn is_interrupt_stop(stop: &Object) -> bool {
+    stop["reason"].as_str() == Some("signal-received")
+        && stop["signal-name"].as_str() == Some("SIGINT")
+}
+
+pub struct GDB {
+    pub mi: gdbmi::GDB,
+    pub breakpoints: BreakPointSet,
+    pub threads: ThreadSet,
+    pub libraries: LibrarySet,
+    pub inferior_state: InferiorState,
+    pub inferior_pid: Option<u64>,
+    pub last_stop: Option<StopEvent>,
+    // Direction of the last execution command issued via execute_movement
+    pub execution_direction: ExecutionDirection,
+    pub remote: Option<RemoteTarget>,
+    // Set if a core dump has been loaded instead of running a live program
+    pub core_dump: Option<CoreDump>,
+    // Exit status of gdb if it terminated without being asked to quit
+    pub unexpected_exit: Option<String>,
+    // Set when the user confirmed that gdb should be started again after an unexpected exit
+    pub respawn_requested: bool,
+    // Number of stops caused by interrupts for executing commands that have not been handled yet
+    own_interrupt_stops: usize,
+    // Number of interrupts for executing commands that did not stop the program in time. The
+    // program has to be resumed once the (late) stop arrives.
+    abandoned_interrupts: usize,
+    // Problems that occurred in the background and should be reported to the user
+    messages: Vec<String>,
+}
+
+pub enum BreakpointOperationError {
+    Busy,
+    ExecutionError(String),
+    MalformedResponse(response::GDBResponseError),
+}
+
+impl fmt::Display for BreakpointOperationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BreakpointOperationError::Busy => write!(f, "Gdb is busy"),
+            BreakpointOperationError::ExecutionError(msg) => write!(f, "{}", msg),
+            BreakpointOperationError::MalformedResponse(e) => {
+                write!(f, "Unexpected response from gdb: {}", e)
+            }
+        }
+    }
+}
+
+impl From<response::GDBResponseError> for BreakpointOperationError {
+    fn from(e: response::GDBResponseError) -> Self {
+        BreakpointOperationError::MalformedResponse(e)
+    }
+}
+
+impl GDB {
+    pub fn new(mi: gdbmi::GDB) -> Self {
+        GDB {
+            mi: mi,
+            breakpoints: BreakPointSet::new(),
+            threads: ThreadSet::new(),
+            libraries: LibrarySet::new(),
+            inferior_state: InferiorState::NotStarted,
+            inferior_pid: None,
+            last_stop: None,
+            execution_direction: ExecutionDirection::Forward,
+            remote: None,
+            core_dump: None,
+            unexpected_exit: None,
+            respawn_requested: false,
+            own_interrupt_stops: 0,
+            abandoned_interrupts: 0,
+            messages: Vec::new(),
+        }
+    }
+
+    // Interrupt the program and wait for it to stop. Returns whether the stop was caused by the
+    // interrupt, i.e., whether execution should be resumed afterwards. (The program may have
+    // stopped for another reason in the meantime, e.g., at a breakpoint.)
+    fn interrupt_for_command(&mut self) -> Result<bool, ExecuteError> {
+        self.mi
+            .interrupt_execution()
+            .map_err(|_| ExecuteError::Busy)?;
+        if !self.mi.wait_until_stopped(INTERRUPT_TIMEOUT) {
+            // The interrupt has been sent nevertheless, so the stop may still arrive.
+            self.abandoned_interrupts += 1;
+            return Err(ExecuteError::Busy);
+        }
+        let own_stop = self
+            .mi
+            .last_stop()
+            .map(|stop| is_interrupt_stop(&stop))
+            .unwrap_or(false);
+        if own_stop {
+            self.own_interrupt_stops += 1;
+        }
+        Ok(own_stop)
+    }
+
+    // Run f with a stopped program: If the program is running, it is interrupted and resumed
+    // after f has finished. Commands that are issued together should be executed in a single f in
+    // order to interrupt the program only once.
+    pub fn with_stopped_program<R, F: FnOnce(&mut gdbmi::GDB) -> Result<R, ExecuteError>>(
+        &mut self,
+        f: F,
+    ) -> Result<R, ExecuteError> {
+        if self.mi.accepts_commands() {
+            return f(&mut self.mi);
+        }
+        let resume = self.interrupt_for_command()?;
+        let result = f(&mut self.mi);
+        if resume {
+            // gdb executes commands in order, so any command issued by f (even asynchronously)
+            // is finished before execution resumes.
+            self.resume_after_interrupt();
+        }
+        result
+    }
+
+    fn resume_after_interrupt(&mut self) {
+        let error = match self.mi.execute(MiCommand::exec_continue()) {
+            Ok(ref result) if result.class == ResultClass::Error => result.results["msg"]
+                .as_str()
+                .unwrap_or("unknown error")
+                .to_owned(),
+            Ok(_) => return,
+            Err(e) => format!("{:?}", e),
+        };
+        warn!("Failed to resume execution: {}", error);
+        self.messages.push(format!(
+            "The program was interrupted to execute a command, but could not be resumed: {}",
+            error
+        ));
+    }
+
+    // Take the messages about problems that occurred in the background (e.g., while executing
+    // commands in a running program).
+    pub fn take_messages(&mut self) -> Vec<String> {
+        ::std::mem::replace(&mut self.messages, Vec::new())
+    }
+
+    // Like gdbmi::GDB::execute, but temporarily interrupts the program if it is running.
+    pub fn execute<C: Borrow<MiCommand>>(
+        &mut self,
+        command: C,
+    ) -> Result<ResultRecord, ExecuteError> {
+        self.with_stopped_program(|mi| mi.execute(command))
+    }
+
+    // Like gdbmi::GDB::execute_async, but temporarily interrupts the program if it is running.
+    pub fn execute_async<C: Borrow<MiCommand>>(
+        &mut self,
+        command: C,
+        timeout: Option<::std::time::Duration>,
+    ) -> Result<Token, ExecuteError> {
+        self.with_stopped_program(|mi| mi.execute_async(command, timeout))
+    }
+
+    // Check if the stop was caused by interrupting the program for executing a command (see
+    // execute). These stops should be ignored, the program has already been resumed (or is
+    // resumed now if the stop arrived too late).
+    pub fn is_own_interrupt_stop(&mut self, stop: &Object) -> bool {
+        if self.own_interrupt_stops > 0 && is_interrupt_stop(stop) {
+            self.own_interrupt_stops -= 1;
+            true
+        } else if self.abandoned_interrupts > 0 && is_interrupt_stop(stop) {
+            // The command that required the interrupt has been given up, only resume.
+            self.abandoned_interrupts -= 1;
+            self.resume_after_interrupt();
+            true
+        } else {
+            false
+        }
+    }
+
+    // Commands that run or modify the program make no sense for a core dump.
+    pub fn check_live_program(&self) -> Result<(), response::GDBResponseError> {
+        if self.core_dump.is_some() {
+            Err(response::GDBResponseError::Other(
+                "Not available for core dumps: The program is not running.".to_owned(),
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    // Switch to post-mortem mode after a core file has been loaded: Determine the signal that
+    // terminated the program from the siginfo stored in the core dump.
+    pub fn load_core_dump(&mut self) -> Result<&CoreDump, response::GDBResponseError> {
+        let signal = self
+            .evaluate_address("$_siginfo.si_signo")
+            .ok()
+            .map(|signal| signal.0 as u32);
+        let fault_address = match signal {
+            Some(signal) if is_fault_signal(signal) => self
+                .evaluate_address("$_siginfo._sifields._sigfault.si_addr")
+                .ok(),
+            _ => None,
+        };
+        self.core_dump = Some(CoreDump {
+            signal: signal,
+            fault_address: fault_address,
+        });
+        self.refresh_threads()?;
+        Ok(self.core_dump.as_ref().unwrap())
+    }
+
+    // Resume the program with an exec-* command (e.g., exec_next) in the given direction. Unlike
+    // other commands, this does not interrupt a running program.
+    pub fn execute_movement(
+        &mut self,
+        command: MiCommand,
+        direction: ExecutionDirection,
+    ) -> Result<(), response::GDBResponseError> {
+        self.check_live_program()?;
+        if !self.mi.accepts_commands() {
+            return Err(ExecuteError::Busy.into());
+        }
+        let command = match direction {
+            ExecutionDirection::Forward => command,
+            ExecutionDirection::Reverse => command.reverse(),
+        };
+        let result = self.mi.execute(command)?;
+        match result.class {
+            ResultClass::Error => Err(response::GDBResponseError::Other(
+                result.results["msg"]
+                    .as_str()
+                    .map(|s| s.to_owned())
+                    .unwrap_or_else(|| result.results.dump()),
+            )),
+            _ => {
+                self.execution_direction = direction;
+                Ok(())
+            }
+        }
+    }
+
+    // Move the program counter to the location. gdb resumes execution at the new location, so a
+    // temporary breakpoint is set there to stop the program before it executes anything.
+    pub fn jump(&mut self, location: BreakPointLocation) -> Result<(), response::GDBResponseError> {
+        self.check_live_program()?;
+        if !self.mi.accepts_commands() {
+            return Err(ExecuteError::Busy.into());
+        }
+        let number = self
+            .execute_insert_breakpoint(MiCommand::insert_temporary_breakpoint(location))
+            .map_err(|e| response::GDBResponseError::Other(e.to_string()))?;
+        let result =
+            self.execute_movement(MiCommand::exec_jump(location), ExecutionDirection::Forward);
+        if result.is_err() {
+            if let Err(e) = self.delete_breakpoints(::std::iter::once(number)) {
+                warn!("Failed to delete temporary breakpoint {}: {}", number, e);
+            }
+        }
+        result
+    }
+
+    // Connect to a remote target. On failure, the target is remembered as disconnected so that
+    // the connection can be retried later.
+    pub fn connect_remote(
+        &mut self,
+        address: &str,
+        extended: bool,
+    ) -> Result<(), response::GDBResponseError> {
+        let result = self.execute(MiCommand::target_select_remote(address, extended));
+        let connected = match result {
+            // Older versions of gdb report ^connected, newer ones ^done.
+            Ok(ResultRecord {
+                class: ResultClass::Connected,
+                ..
+            })
+            | Ok(ResultRecord {
+                class: ResultClass::Done,
+                ..
+            }) => Ok(()),
+            Ok(ResultRecord { results, .. }) => Err(response::GDBResponseError::Other(
+                results["msg"]
+                    .as_str()
+                    .map(|s| s.to_owned())
+                    .unwrap_or_else(|| results.dump()),
+            )),
+            Err(e) => Err(e.into()),
+        };
+        self.remote = Some(RemoteTarget {
+            address: address.to_owned(),
+            extended: extended,
+            connected: connected.is_ok(),
+        });
+        connected
+    }
+
+    pub fn reconnect_remote(&mut self) -> Result<(), response::GDBResponseError> {
+        match self.remote.clone() {
+            Some(remote) => self.connect_remote(&remote.address, remote.extended),
+            None => Err(response::GDBResponseError::Other(
+                "Not connected to a remote target".to_owned(),
+            )),
+        }
+    }
+
+    pub fn handle_remote_disconnect(&mut self) {
+        if let Some(ref mut remote) = self.remote {
+            remote.connected = false;
+        }
+        self.threads.replace_all(Vec::new(), None);
+        self.inferior_pid = None;
+        match self.inferior_state {
+            InferiorState::Running | InferiorState::Stopped => {
+                self.inferior_state = InferiorState::NotStarted
+            }
+            InferiorState::NotStarted | InferiorState::Exited(_) => {}
+        }
+    }
+
+    pub fn attach(&mut self, pid: u32) -> Result<(), response::GDBResponseError> {
+        let result = self.execute(MiCommand::target_attach(pid))?;
+        match result.class {
+            ResultClass::Error => {
+                let msg = result.results["msg"].as_str().unwrap_or("unknown error");
+                let hint = if msg.contains("Operation not permitted") {
+                    let ptrace_scope = ::std::fs::read_to_string(signals::PTRACE_SCOPE_PATH)
+                        .ok()
+                        .and_then(|scope| scope.trim().parse::<u32>().ok());
+                    format!(" {}", signals::ptrace_permission_hint(ptrace_scope))
+                } else {
+                    String::new()
+                };
+                Err(response::GDBResponseError::Other(format!(
+                    "Cannot attach to process {}: {}{}",
+                    pid, msg, hint
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn detach(&mut self) -> Result<(), response::GDBResponseError> {
+        let result = self.execute(MiCommand::target_detach())?;
+        match result.class {
+            ResultClass::Error => Err(response::GDBResponseError::Other(
+                result.results["msg"]
+                    .as_str()
+                    .map(|s| s.to_owned())
+                    .unwrap_or_else(|| result.results.dump()),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn kill(&mut self) {
+        if let Err(e) = self.mi.interrupt_execution() {
+            warn!("Failed to interrupt gdb: {}", e);
+        }
+        self.mi.execute_later(&gdbmi::commands::MiCommand::exit());
+    }
+
+    pub fn insert_breakpoint(
+        &mut self,
+        location: BreakPointLocation,
+    ) -> Result<(), BreakpointOperationError> {
+        self.insert_breakpoint_with_number(location).map(|_| ())
+    }
+
+    fn insert_breakpoint_with_number(
+        &mut self,
+        location: BreakPointLocation,
+    ) -> Result<BreakPointNumber, BreakpointOperationError> {
+        self.execute_insert_breakpoint(MiCommand::insert_breakpoint(location))
+    }
+
+    fn execute_insert_breakpoint(
+        &mut self,
+        command: MiCommand,
+    ) -> Result<BreakPointNumber, BreakpointOperationError> {
+        let results = self.execute_breakpoint_command(command)?;
+        self.handle_breakpoint_event(BreakPointEvent::Created, &results)?;
+        match results["bkpt"] {
+            JsonValue::Object(ref bkpt) => Ok(response::get_breakpoint_number_obj(bkpt, "number")?),
+            ref other => Err(BreakpointOperationError::ExecutionError(format!(
+                "Malformed breakpoint description: {}",
+                other.dump()
+            ))),
+        }
+    }
+
+    // Insert the breakpoints (including their conditions, commands, etc.) of a previous gdb
+    // session, e.g., after gdb has been restarted. Returns the breakpoints that could not be
+    // restored.
+    pub fn restore_breakpoints(
+        &mut self,
+        old: &BreakPointSet,
+    ) -> Vec<(BreakPointNumber, BreakpointOperationError)> {
+        // Temporary breakpoints belong to an operation of the old session (e.g., jump).
+        let mut majors = old
+            .values()
+            .filter(|bp| bp.number.minor.is_none() && !bp.temporary)
+            .collect::<Vec<_>>();
+        majors.sort_by_key(|bp| bp.number.major);
+        let mut failures = Vec::new();
+        for bp in majors {
+            if let Err(e) = self.restore_breakpoint(bp) {
+                failures.push((bp.number, e));
+            }
+        }
+        failures
+    }
+
+    fn restore_breakpoint(&mut self, bp: &BreakPoint) -> Result<(), BreakpointOperationError> {
+        let number = match (bp.kind, &bp.expression) {
+            (BreakPointKind::Watchpoint(mode), &Some(ref expression)) => {
+                self.insert_watchpoint(expression, mode)?
+            }
+            _ => {
+                let spec = if let Some(ref location) = bp.original_location {
+                    location.clone()
+                } else if let Some(ref pos) = bp.src_pos {
+                    format!("{}:{}", pos.file.to_string_lossy(), pos.line)
+                } else if let Some(ref function) = bp.function {
+                    function.clone()
+                } else if let Some(address) = bp.address {
+                    format!("*{}", address)
+                } else {
+                    return Err(BreakpointOperationError::ExecutionError(
+                        "Unknown breakpoint location".to_owned(),
+                    ));
+                };
+                self.insert_breakpoint_with_number(BreakPointLocation::Spec(&spec))?
+            }
+        };
+        if bp.condition.is_some() {
+            self.set_breakpoint_condition(number, bp.condition.as_ref().map(|c| c.as_str()))?;
+        }
+        if bp.ignore_count > 0 {
+            self.set_breakpoint_ignore_count(number, bp.ignore_count)?;
+        }
+        if !bp.commands.is_empty() {
+            self.set_breakpoint_commands(number, &bp.commands)?;
+        }
+        if !bp.enabled {
+            self.set_breakpoint_enabled(number, false)?;
+        }
+        Ok(())
+    }
+
+    pub fn insert_watchpoint(
+        &mut self,
+        expression: &str,
+        mode: WatchMode,
+    ) -> Result<BreakPointNumber, BreakpointOperationError> {
+        let results =
+            self.execute_breakpoint_command(MiCommand::insert_watchpoint(expression, mode))?;
+        // The result only contains the number and expression of the new watchpoint, so we have
+        // to ask for the full description.
+        let number = ["wpt", "hw-rwpt", "hw-awpt"]
+            .iter()
+            .filter_map(|key| results[*key]["number"].as_str())
+            .next()
+            .and_then(|n| n.parse::<BreakPointNumber>().ok())
+            .ok_or_else(|| {
+                BreakpointOperationError::ExecutionError(format!(
+                    "Malformed watchpoint description: {}",
+                    results.dump()
+                ))
+            })?;
+        self.refresh_breakpoint(number)?;
+        Ok(number)
+    }
+
+    pub fn delete_breakpoints<I: Clone + Iterator<Item = BreakPointNumber>>(
+        &mut self,
+        bp_numbers: I,
+    ) -> Result<(), BreakpointOperationError> {
+        self.execute_breakpoint_command(MiCommand::delete_breakpoints(bp_numbers.clone()))?;
+        let major_to_delete = bp_numbers.map(|n| n.major).collect::<HashSet<usize>>();
+        let bkpts_to_delete = self
+            .breakpoints
+            .map
+            .keys()
+            .filter_map(|&k| {
+                if major_to_delete.contains(&k.major) {
+                    Some(k)
+                } else {
+                    None
+                }
+            })
+            .collect::<Vec<BreakPointNumber>>();
+        for bkpt in bkpts_to_delete {
+            self.breakpoints.remove_breakpoint(bkpt);
+        }
+        Ok(())
+    }
+
+    fn execute_breakpoint_command(
+        &mut self,
+        command: MiCommand,
+    ) -> Result<Object, BreakpointOperationError> {
+        let bp_result = self.execute(command).map_err(|e| match e {
+            ExecuteError::Busy => BreakpointOperationError::Busy,
+            ExecuteError::Quit => BreakpointOperationError::ExecutionError("GDB quit".to_owned()),
+        })?;
+        match bp_result.class {
+            ResultClass::Done => Ok(bp_result.results),
+            ResultClass::Error => Err(BreakpointOperationError::ExecutionError(
+                bp_result
+                    .results
+                    .get("msg")
+                    .and_then(|msg_obj| msg_obj.as_str())
+                    .map(|s| s.to_owned())
+                    .unwrap_or_else(|| bp_result.results.dump()),
+            )),
+            other => Err(BreakpointOperationError::ExecutionError(format!(
+                "Unexpected resultclass: {:?}",
+                other
+            ))),
+        }
+    }
+
+    // Query the current state of the breakpoint (and all of its locations) from gdb.
+    // Modifications via mi commands do not generate breakpoint-modified notifications.
+    pub fn refresh_breakpoint(
+        &mut self,
+        number: BreakPointNumber,
+    ) -> Result<(), BreakpointOperationError> {
+        let major = BreakPointNumber {
+            major: number.major,
+            minor: None,
+        };
+        let results = self.execute_breakpoint_command(MiCommand::break_info(major))?;
+        self.breakpoints.remove_breakpoint(major);
+        for entry in results["BreakpointTable"]["body"].members() {
+            for bp in BreakPoint::all_from_json(entry)? {
+                self.breakpoints.update_breakpoint(bp);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn set_breakpoint_enabled(
+        &mut self,
+        number: BreakPointNumber,
+        enabled: bool,
+    ) -> Result<(), BreakpointOperationError> {
+        let numbers = Some(number).into_iter();
+        self.execute_breakpoint_command(if enabled {
+            MiCommand::break_enable(numbers)
+        } else {
+            MiCommand::break_disable(numbers)
+        })?;
+        self.refresh_breakpoint(number)
+    }
+
+    pub fn set_breakpoint_condition(
+        &mut self,
+        number: BreakPointNumber,
+        condition: Option<&str>,
+    ) -> Result<(), BreakpointOperationError> {
+        self.execute_breakpoint_command(MiCommand::break_condition(number, condition))?;
+        self.refresh_breakpoint(number)
+    }
+
+    pub fn set_breakpoint_ignore_count(
+        &mut self,
+        number: BreakPointNumber,
+        ignore_count: usize,
+    ) -> Result<(), BreakpointOperationError> {
+        self.execute_breakpoint_command(MiCommand::break_after(number, ignore_count))?;
+        self.refresh_breakpoint(number)
+    }
+
+    pub fn set_breakpoint_commands<S: AsRef<str>>(
+        &mut self,
+        number: BreakPointNumber,
+        commands: &[S],
+    ) -> Result<(), BreakpointOperationError> {
+        self.execute_breakpoint_command(MiCommand::break_commands(number, commands))?;
+        self.refresh_breakpoint(number)
+    }
+
+    pub fn handle_breakpoint_event(
+        &mut self,
+        bp_type: BreakPointEvent,
+        info: &Object,
+    ) -> Result<(), response::GDBResponseError> {
+        match bp_type {
+            BreakPointEvent::Created | BreakPointEvent::Modified => {
+                // Parse all breakpoints first so that a malformed description does not leave
+                // us with a partially updated breakpoint.
+                let bps = BreakPoint::all_from_json(&info["bkpt"])?;
+                // The description contains all current locations. Locations that are missing
+                // (e.g., because a library was unloaded) are gone.
+                for bp in bps.iter().filter(|bp| bp.number.minor.is_none()) {
+                    self.breakpoints.remove_breakpoint(bp.number);
+                }
+                for bp in bps {
+                    self.breakpoints.update_breakpoint(bp);
+                }
+            }
+            BreakPointEvent::Deleted => {
+                let id = response::get_breakpoint_number_obj(info, "id")?;
+                self.breakpoints.remove_breakpoint(id);
+            }
+        }
+        Ok(())
+    }
+
+    // Query all threads (including their current frames) from gdb.
+    pub fn refresh_threads(&mut self) -> Result<(), response::GDBResponseError> {
+        let result = self.mi.execute(MiCommand::thread_info(None))?;
+        if result.class != ResultClass::Done {
+            return Err(response::GDBResponseError::Other(format!(
+                "Unexpected result class: {:?}",
+                result.class
+            )));
+        }
+        let threads = result.results["threads"]
+            .members()
+            .map(Thread::from_json)
+            .collect::<Result<Vec<Thread>, _>>()?;
+        let current = result.results["current-thread-id"]
+            .as_str()
+            .and_then(|id| id.parse::<u64>().ok());
+        self.threads.replace_all(threads, current);
+        Ok(())
+    }
+
+    pub fn handle_thread_event(&mut self, event: ThreadEvent, info: &Object) {
+        let id = info["id"].as_str().and_then(|id| id.parse::<u64>().ok());
+        match event {
+            ThreadEvent::Created => {
+                if let Some(id) = id {
+                    self.threads.map.insert(
+                        id,
+                        Thread {
+                            id: id,
+                            target_id: None,
+                            name: None,
+                            state: ThreadState::Running,
+                            frame: None,
+                            core: None,
+                        },
+                    );
+                    self.threads.notify_change();
+                }
+            }
+            ThreadEvent::Exited => {
+                if let Some(id) = id {
+                    self.threads.map.remove(&id);
+                    if self.threads.current == Some(id) {
+                        self.threads.current = None;
+                    }
+                    self.threads.notify_change();
+                }
+            }
+            ThreadEvent::Selected => {
+                self.threads.current = id;
+                self.threads.notify_change();
+            }
+            ThreadEvent::GroupStarted => {
+                // A new process was started, libraries of a previous run are gone.
+                if let Some(group) = info["id"].as_str() {
+                    self.libraries.remove_thread_group(group);
+                }
+                self.inferior_pid = info["pid"].as_str().and_then(|pid| pid.parse::<u64>().ok());
+                self.inferior_state = InferiorState::Running;
+                self.execution_direction = ExecutionDirection::Forward;
+            }
+            ThreadEvent::GroupExited => {
+                self.threads.replace_all(Vec::new(), None);
+                if let Some(group) = info["id"].as_str() {
+                    self.libraries.remove_thread_group(group);
+                }
+                self.inferior_pid = None;
+                // The exit code is missing if the process was killed (by a signal or by gdb).
+                self.inferior_state = InferiorState::Exited(
+                    info["exit-code"]
+                        .as_str()
+                        .and_then(|code| u32::from_str_radix(code, 8).ok()),
+                );
+                // gdbserver terminates with the program unless the extended protocol is used.
+                if let Some(ref mut remote) = self.remote {
+                    if !remote.extended {
+                        remote.connected = false;
+                    }
+                }
+            }
+            ThreadEvent::GroupAdded | ThreadEvent::GroupRemoved => {}
+        }
+    }
+
+    pub fn handle_library_event(&mut self, event: LibraryEvent, info: &Object) {
+        match event {
+            LibraryEvent::Loaded => match Library::from_json(&JsonValue::Object(info.clone())) {
+                Ok(library) => self.libraries.update_library(library),
+                Err(e) => warn!("Malformed library-loaded notification: {:?}", e),
+            },
+            LibraryEvent::Unloaded => {
+                if let Some(id) = info["id"].as_str() {
+                    self.libraries.remove_library(id);
+                }
+            }
+        }
+    }
+
+    // Query all shared libraries from gdb, e.g., after their symbols have been loaded (which is
+    // not announced by gdb).
+    pub fn refresh_libraries(&mut self) -> Result<(), response::GDBResponseError> {
+        let result = self.execute(MiCommand::file_list_shared_libraries())?;
+        if result.class != ResultClass::Done {
+            return Err(response::GDBResponseError::Other(
+                response::get_str_obj(&result.results, "msg")?.to_owned(),
+            ));
+        }
+        let libraries = result.results["shared-libraries"]
+            .members()
+            .map(Library::from_json)
+            .collect::<Result<Vec<Library>, _>>()?;
+        self.libraries.replace_all(libraries);
+        Ok(())
+    }
+
+    // Load the symbols of a single library (which is useful if auto-solib-add is off).
+    pub fn load_library_symbols(
+        &mut self,
+        target_name: &str,
+    ) -> Result<(), response::GDBResponseError> {
+        let result = self.execute(MiCommand::cli_exec(&format!(
+            "sharedlibrary {}",
+            library_name_regex(target_name)
+        )))?;
+        if result.class != ResultClass::Done {
+            return Err(response::GDBResponseError::Other(
+                response::get_str_obj(&result.results, "msg")?.to_owned(),
+            ));
+        }
+        self.refresh_libraries()
+    }
+
+    // Handle *running records, which refer to a single thread or "all".
+    pub fn handle_running_event(&mut self, info: &Object) {
+        let id = info["thread-id"]
+            .as_str()
+            .and_then(|id| id.parse::<u64>().ok());
+        self.threads.set_state(id, ThreadState::Running);
+        self.inferior_state = InferiorState::Running;
+    }
+
+    // Handle *stopped records, which list the stopped threads (or "all").
+    pub fn handle_stopped_event(&mut self, info: &Object) {
+        match &info["stopped-threads"] {
+            &JsonValue::Array(ref ids) => {
+                for id in ids
+                    .iter()
+                    .filter_map(|id| id.as_str())
+                    .filter_map(|id| id.parse::<u64>().ok())
+                {
+                    self.threads.set_state(Some(id), ThreadState::Stopped);
+                }
+            }
+            _ => self.threads.set_state(None, ThreadState::Stopped),
+        }
+        let stop = StopEvent::from_results(info);
+        self.inferior_state = match stop.reason {
+            Some(StopReason::Exited) => InferiorState::Exited(stop.exit_code),
+            Some(StopReason::ExitedNormally) => InferiorState::Exited(Some(0)),
+            Some(StopReason::ExitedSignalled) => InferiorState::Exited(None),
+            // In non-stop mode, other threads may still be running
+            _ if self.threads.values().any(|t| t.state == ThreadState::Running) => {
+                InferiorState::Running
+            }
+            _ => InferiorState::Stopped,
+        };
+        self.last_stop = Some(stop);
+    }
+
+    fn execute_thread_command(
+        &mut self,
+        command: MiCommand,
+    ) -> Result<(), response::GDBResponseError> {
+        let result = self.mi.execute(command)?;
+        match result.class {
+            ResultClass::Done | ResultClass::Running => Ok(()),
+            _ => Err(response::GDBResponseError::Other(
+                response::get_str_obj(&result.results, "msg")?.to_owned(),
+            )),
+        }
+    }
+
+    // Stop the thread. In all-stop mode, all threads are stopped.
+    pub fn interrupt_thread(&mut self, id: u64) -> Result<(), response::GDBResponseError> {
+        self.check_live_program()?;
+        if self.mi.is_non_stop() {
+            self.execute_thread_command(MiCommand::exec_interrupt().on_thread(id))
+        } else {
+            self.mi.interrupt_execution().map_err(|e| {
+                response::GDBResponseError::Other(format!("Cannot interrupt gdb: {}", e))
+            })
+        }
+    }
+
+    // Resume the thread. In all-stop mode, all threads are resumed.
+    pub fn continue_thread(&mut self, id: u64) -> Result<(), response::GDBResponseError> {
+        self.check_live_program()?;
+        self.execute_thread_command(MiCommand::exec_continue().on_thread(id))
+    }
+
+    // Select the thread and return the description of its current frame.
+    pub fn select_thread(&mut self, id: u64) -> Result<Object, response::GDBResponseError> {
+        let result = self.mi.execute(MiCommand::thread_select(id))?;
+        if result.class != ResultClass::Done {
+            return Err(response::GDBResponseError::Other(
+                response::get_str_obj(&result.results, "msg")?.to_owned(),
+            ));
+        }
+        self.threads.current = Some(id);
+        self.threads.notify_change();
+        match &result.results["frame"] {
+            &JsonValue::Object(ref frame) => Ok(frame.clone()),
+            _ => Err(response::GDBResponseError::MissingField(
+                "frame",
+                JsonValue::Object(result.results.clone()),
+            )),
+        }
+    }
+
+    // Warning: This is a hack, as gdbmi does not currently offer a command to query the current target
+    // May not work and can break at any time.
+    pub fn get_target(&mut self) -> Result<Option<PathBuf>, ExecuteError> {
+        let result = self.mi.execute(MiCommand::list_thread_groups(false, &[]))?;
+        if result.class == ResultClass::Done {
+            Ok(result.results["groups"]
+                .members()
+                .filter_map(|thread| thread["executable"].as_str())
+                .next()
+                .map(|exec| exec.into()))
+        } else {
+            Ok(None)
+        }
+    }
+
+    // Get (at most max_frames of) the frames of the current thread, including argument values.
+    pub fn get_backtrace(
+        &mut self,
+        max_frames: u64,
+    ) -> Result<Vec<StackFrame>, response::GDBResponseError> {
+        self.get_thread_backtrace(None, max_frames)
+    }
+
+    // Like get_backtrace, but for the given thread (or the current thread if None).
+    pub fn get_thread_backtrace(
+        &mut self,
+        thread: Option<u64>,
+        max_frames: u64,
+    ) -> Result<Vec<StackFrame>, response::GDBResponseError> {
+        let on_thread = |command: MiCommand| match thread {
+            Some(id) => command.on_thread(id),
+            None => command,
+        };
+        let levels = Some((0, max_frames.checked_sub(1).unwrap_or(0)));
+        let frames_result = self
+            .mi
+            .execute(on_thread(MiCommand::stack_list_frames(levels)))?;
+        if frames_result.class != ResultClass::Done {
+            return Err(response::GDBResponseError::Other(
+                response::get_str_obj(&frames_result.results, "msg")?.to_owned(),
+            ));
+        }
+        let mut frames = frames_result.results["stack"]
+            .members()
+            .map(StackFrame::from_json)
+            .collect::<Result<Vec<StackFrame>, _>>()?;
+
+        let args_result = self.mi.execute(on_thread(MiCommand::stack_list_arguments(
+            ValuePrintMode::SimpleValues,
+            levels,
+        )))?;
+        if args_result.class == ResultClass::Done {
+            for frame_args in args_result.results["stack-args"].members() {
+                let level = response::get_u64(frame_args, "level")?;
+                if let Some(frame) = frames.iter_mut().find(|f| f.level == level) {
+                    frame.arguments = frame_args["args"]
+                        .members()
+                        .map(|arg| {
+                            if let Some(name) = arg.as_str() {
+                                (name.to_owned(), None)
+                            } else {
+                                (
+                                    arg["name"].as_str().unwrap_or("?").to_owned(),
+                                    arg["value"].as_str().map(|v| v.to_owned()),
+                                )
+                            }
+                        })
+                        .collect();
+                }
+            }
+        }
+        Ok(frames)
+    }
+
+    // Get arguments and locals (including their values) of the selected frame.
+    pub fn get_frame_variables(&mut self) -> Result<Vec<Variable>, response::GDBResponseError> {
+        let result = self
+            .mi
+            .execute(MiCommand::stack_list_variables(ValuePrintMode::AllValues))?;
+        if result.class != ResultClass::Done {
+            return Err(response::GDBResponseError::Other(
+                response::get_str_obj(&result.results, "msg")?.to_owned(),
+            ));
+        }
+        result.results["variables"]
+            .members()
+            .map(|var| {
+                Ok(Variable {
+                    name: response::get_str(var, "name")?.to_owned(),
+                    is_argument: var["arg"].as_str() == Some("1"),
+                    value: var["value"].as_str().map(|v| v.to_owned()),
+                })
+            })
+            .collect()
+    }
+
+    // Names of all registers, indexed by register number. Unused numbers have empty names.
+    pub fn get_register_names(&mut self) -> Result<Vec<String>, response::GDBResponseError> {
+        let result = self.mi.execute(MiCommand::data_list_register_names())?;
+        if result.class != ResultClass::Done {
+            return Err(response::GDBResponseError::Other(
+                response::get_str_obj(&result.results, "msg")?.to_owned(),
+            ));
+        }
+        Ok(result.results["register-names"]
+            .members()
+            .map(|name| name.as_str().unwrap_or("").to_owned())
+            .collect())
+    }
+
+    pub fn get_registers(
+        &mut self,
+        names: &[String],
+        format: RegisterFormat,
+    ) -> Result<Vec<Register>, response::GDBResponseError> {
+        let result = self
+            .mi
+            .execute(MiCommand::data_list_register_values(format))?;
+        if result.class != ResultClass::Done {
+            return Err(response::GDBResponseError::Other(
+                response::get_str_obj(&result.results, "msg")?.to_owned(),
+            ));
+        }
+        let mut registers = Vec::new();
+        for value in result.results["register-values"].members() {
+            let number = response::get_u64(value, "number")? as usize;
+            match names.get(number) {
+                Some(name) if !name.is_empty() => registers.push(Register {
+                    number: number,
+                    name: name.clone(),
+                    value: response::get_str(value, "value")?.to_owned(),
+                }),
+                _ => {}
+            }
+        }
+        Ok(registers)
+    }
+
+    // Numbers of the registers that changed since the last stop.
+    pub fn get_changed_registers(&mut self) -> Result<Vec<usize>, response::GDBResponseError> {
+        let result = self.mi.execute(MiCommand::data_list_changed_registers())?;
+        if result.class != ResultClass::Done {
+            return Err(response::GDBResponseError::Other(
+                response::get_str_obj(&result.results, "msg")?.to_owned(),
+            ));
+        }
+        Ok(result.results["changed-registers"]
+            .members()
+            .filter_map(|n| n.as_str().and_then(|n| n.parse::<usize>().ok()))
+            .collect())
+    }
+
+    // Evaluate an expression (such as "&var" or "buf") to the address it denotes.
+    pub fn evaluate_address(
+        &mut self,
+        expression: &str,
+    ) -> Result<Address, response::GDBResponseError> {
+        let result = self.execute(MiCommand::data_evaluate_expression(format!(
+            "(unsigned long)({})",
+            expression
+        )))?;
+        if result.class != ResultClass::Done {
+            return Err(response::GDBResponseError::Other(
+                response::get_str_obj(&result.results, "msg")?.to_owned(),
+            ));
+        }
+        let value = response::get_str_obj(&result.results, "value")?;
+        value
+            .parse::<usize>()
+            .map(Address)
+            .map_err(|_| response::GDBResponseError::MalformedAddress(value.to_owned()))
+    }
+
+    // Read count bytes starting at begin. Bytes in unreadable regions are None.
+    pub fn read_memory(
+        &mut self,
+        begin: Address,
+        count: usize,
+    ) -> Result<Vec<Option<u8>>, response::GDBResponseError> {
+        let mut bytes = vec![None; count];
+        let result = self.execute(MiCommand::data_read_memory_bytes(begin.0, count))?;
+        if result.class != ResultClass::Done {
+            // Nothing in the range is readable
+            return Ok(bytes);
+        }
+        for region in result.results["memory"].members() {
+            let region_begin = response::get_addr(region, "begin")?;
+            let contents = response::get_str(region, "contents")?;
+            let offset = region_begin.0.checked_sub(begin.0).unwrap_or(0);
+            for (i, pair) in contents.as_bytes().chunks(2).enumerate() {
+                let byte = ::std::str::from_utf8(pair)
+                    .ok()
+                    .and_then(|s| u8::from_str_radix(s, 16).ok());
+                if let Some(slot) = bytes.get_mut(offset + i) {
+                    *slot = byte;
+                }
+            }
+        }
+        Ok(bytes)
+    }
+
+    pub fn write_memory(
+        &mut self,
+        begin: Address,
+        bytes: &[u8],
+    ) -> Result<(), response::GDBResponseError> {
+        self.check_live_program()?;
+        let result = self.execute(MiCommand::data_write_memory_bytes(begin.0, bytes))?;
+        if result.class != ResultClass::Done {
+            return Err(response::GDBResponseError::Other(
+                response::get_str_obj(&result.results, "msg")?.to_owned(),
+            ));
+        }
+        Ok(())
+    }
+
+    // Select the frame at the given level and return its description.
+    pub fn select_frame(&mut self, level: u64) -> Result<Object, response::GDBResponseError> {
+        self.mi.execute_later(MiCommand::select_frame(level));
+        let result = self.mi.execute(MiCommand::stack_info_frame(None))?;
+        if result.class != ResultClass::Done {
+            return Err(response::GDBResponseError::Other(format!(
+                "Unexpected result class: {:?}",
+                result.class
+            )));
+        }
+        match &result.results["frame"] {
+            &JsonValue::Object(ref frame) => Ok(frame.clone()),
+            _ => Err(response::GDBResponseError::MissingField(
+                "frame",
+                JsonValue::Object(result.results.clone()),
+            )),
+        }
+    }
+
+    pub fn get_stack_level(&mut self) -> Result<u64, response::GDBResponseError> {
+        let frame = self.mi.execute(MiCommand::stack_info_frame(None))?;
+        response::get_u64(&frame.results["frame"], "level")
+    }
+
+    pub fn get_stack_depth(&mut self) -> Result<u64, response::GDBResponseError> {
+        let frame = self.mi.execute(MiCommand::stack_info_depth())?;
+        response::get_u64_obj(&frame.results, "depth")
+    }
+}
+
+// Various helper for getting stuff out of gdb response values
+pub mod response {
+    use super::*;
+
+    #[derive(Clone, Debug, PartialEq)]
+    pub enum GDBResponseError {
+        MissingField(&'static str, JsonValue),
+        MalformedAddress(String),
+        Other(String),
+        Execution(ExecuteError),
+    }
+
+    impl From<(::std::num::ParseIntError, String)> for GDBResponseError {
+        fn from((_, s): (::std::num::ParseIntError, String)) -> Self {
+            GDBResponseError::MalformedAddress(s)
+        }
+    }
+    impl From<ExecuteError> for GDBResponseError {
+        fn from(e: ExecuteError) -> Self {
+            GDBResponseError::Execution(e)
+        }
+    }
+
+    impl fmt::Display for GDBResponseError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                GDBResponseError::MissingField(key, obj) => {
+                    write!(f, "Missing field '{}' in {}", key, obj.dump())
+                }
+                GDBResponseError::MalformedAddress(s) => write!(f, "Malformed address '{}'", s),
+                GDBResponseError::Other(msg) => write!(f, "{}", msg),
+                GDBResponseError::Execution(ExecuteError::Busy) => write!(f, "Gdb is busy"),
+                GDBResponseError::Execution(ExecuteError::Quit) => write!(f, "Gdb quit"),
+            }
+        }
+    }
+
+    pub fn get_str<'a>(obj: &'a JsonValue, key: &'static str) -> Result<&'a str, GDBResponseError> {
+        Ok(obj[key]
+            .as_str()
+            .ok_or_else(|| GDBResponseError::MissingField(key, obj.clone()))?)
+    }
+
+    pub fn get_str_obj<'a>(
+        obj: &'a Object,
+        key: &'static str,
+    ) -> Result<&'a str, GDBResponseError> {
+        Ok(obj[key]
+            .as_str()
+            .ok_or_else(|| GDBResponseError::MissingField(key, JsonValue::Object(obj.clone())))?)
+    }
+
+    pub fn get_addr(obj: &JsonValue, key: &'static str) -> Result<Address, GDBResponseError> {
+        let s = get_str(obj, key)?;
+        Ok(Address::parse(s)?)
+    }
+
+    pub fn get_addr_obj(obj: &Object, key: &'static str) -> Result<Address, GDBResponseError> {
+        let s = get_str_obj(obj, key)?;
+        Ok(Address::parse(s)?)
+    }
+
+    pub fn get_u64(obj: &JsonValue, key: &'static str) -> Result<u64, GDBResponseError> {
+        let s = get_str(obj, key)?;
+        Ok(s.parse::<u64>().map_err(|e| {
+            GDBResponseError::Other(format!("Malformed frame description: {:?}", e))
+        })?)
+    }
+
+    pub fn get_u64_obj(obj: &Object, key: &'static str) -> Result<u64, GDBResponseError> {
+        let s = get_str_obj(obj, key)?;
+        Ok(s.parse::<u64>().map_err(|e| {
+            GDBResponseError::Other(format!("Malformed frame description: {:?}", e))
+        })?)
+    }
+
+    pub fn get_breakpoint_number_obj(
+        obj: &Object,
+        key: &'static str,
+    ) -> Result<BreakPointNumber, GDBResponseError> {
+        let s = get_str_obj(obj, key)?;
+        Ok(s.parse::<BreakPointNumber>().map_err(|e| {
+            GDBResponseError::Other(format!("Malformed breakpoint number '{}': {}", s, e))
+        })?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse_object(input: &str) -> Object {
+        match ::json::parse(input).unwrap() {
+            JsonValue::Object(obj) => obj,
+            other => panic!("Not an object: {}", other),
+        }
+    }
+
+    #[test]
+    fn test_watchpoint_stop_message() {
+        assert_eq!(
+            watchpoint_stop_message(&parse_object(
+                r#"{"reason": "watchpoint-trigger", "wpt": {"number": "2", "exp": "x"},
+                "value": {"old": "1", "new": "2"}}"#
+            ))
+            .unwrap(),
+            "Watchpoint 2: x\nOld value = 1\nNew value = 2"
+        );
+        assert_eq!(
+            watchpoint_stop_message(&parse_object(
+                r#"{"reason": "read-watchpoint-trigger", "hw-rwpt": {"number": "3", "exp": "y"},
+                "value": {"value": "5"}}"#
+            ))
+            .unwrap(),
+            "Read watchpoint 3: y\nValue = 5"
+        );
+        assert_eq!(
+            watchpoint_stop_message(&parse_object(r#"{"reason": "end-stepping-range"}"#)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_library() {
+        let library = Library::from_json(&JsonValue::Object(parse_object(
+            r#"{"id": "/lib/libm.so.6", "target-name": "/lib/libm.so.6",
+            "host-name": "/sysroot/lib/libm.so.6", "symbols-loaded": "1", "thread-group": "i1",
+            "ranges": [{"from": "0x7000", "to": "0x8000"}, {"from": "0x9000", "to": "0x9100"}]}"#,
+        )))
+        .unwrap();
+        assert_eq!(library.host_name, "/sysroot/lib/libm.so.6");
+        assert!(library.symbols_loaded);
+        assert_eq!(library.thread_group, Some("i1".to_owned()));
+        assert!(library.contains(Address(0x7000)));
+        assert!(library.contains(Address(0x90ff)));
+        assert!(!library.contains(Address(0x8000)));
+
+        let library = Library::from_json(&JsonValue::Object(parse_object(
+            r#"{"id": "libfoo.so", "symbols-loaded": "0",
+            "low-address": "0x1000", "high-address": "0x2000"}"#,
+        )))
+        .unwrap();
+        assert_eq!(library.target_name, "libfoo.so");
+        assert!(!library.symbols_loaded);
+        assert_eq!(library.ranges, vec![(Address(0x1000), Address(0x2000))]);
+    }
+
+    #[test]
+    fn test_remote_disconnect_message() {
+        assert!(is_remote_disconnect_message("Remote connection closed\n"));
+        assert!(is_remote_disconnect_message(
+            "Remote communication error.  Target disconnected.: Connection reset by peer.\n"
+        ));
+        assert!(!is_remote_disconnect_message("Remote debugging using localhost:1234\n"));
+        assert!(!is_remote_disconnect_message("Breakpoint 1, main () at main.c:3\n"));
+    }
+
+    #[test]
+    fn test_remote_target_display() {
+        let remote = RemoteTarget {
+            address: "localhost:1234".to_owned(),
+            extended: true,
+            connected: false,
+        };
+        assert_eq!(remote.to_string(), "extended-remote localhost:1234 (disconnected)");
+    }
+
+    #[test]
+    fn test_core_dump_description() {
+        let core = CoreDump {
+            signal: Some(11),
+            fault_address: Some(Address(0)),
+        };
+        assert_eq!(core.signal_name(), Some("SIGSEGV".to_owned()));
+        assert_eq!(
+            core.description(),
+            "Program terminated with signal SIGSEGV, Segmentation fault. Fault address: 0x0."
+        );
+        let core = CoreDump {
+            signal: Some(6),
+            fault_address: None,
+        };
+        assert_eq!(core.description(), "Program terminated with signal SIGABRT, Aborted.");
+        let core = CoreDump {
+            signal: Some(42),
+            fault_address: None,
+        };
+        assert_eq!(core.signal_name(), Some("signal 42".to_owned()));
+        assert_eq!(core.description(), "Program terminated with signal 42.");
+        let core = CoreDump {
+            signal: None,
+            fault_address: None,
+        };
+        assert_eq!(core.signal_name(), None);
+    }
+
+    #[test]
+    fn test_library_name_regex() {
+        assert_eq!(
+            library_name_regex("/usr/lib/libstdc++.so.6"),
+            r"^/usr/lib/libstdc\+\+\.so\.6$"
+        );
+    }
+
+    fn parse_mi(line: &str) -> Object {
+        ::gdbmi::output::parse_results(line).unwrap()
+    }
+
+    #[test]
+    fn test_address_parse() {
+        assert_eq!(Address::parse("0x00007ffff7e3e000"), Ok(Address(0x7ffff7e3e000)));
+        assert!(Address::parse("<MULTIPLE>").is_err());
+        assert!(Address::parse("<PENDING>").is_err());
+        assert!(Address::parse("").is_err());
+    }
+
+    #[test]
+    fn test_breakpoints_from_mi() {
+        // gdb 8 - 12: a single location
+        let results = parse_mi(
+            r#"^done,bkpt={number="1",type="breakpoint",disp="keep",enabled="y",addr="0x0000000000001139",func="main",file="main.c",fullname="/tmp/main.c",line="3",thread-groups=["i1"],times="0",original-location="main.c:3"}"#,
+        );
+        let bps = BreakPoint::all_from_json(&results["bkpt"]).unwrap();
+        assert_eq!(bps.len(), 1);
+        assert_eq!(bps[0].number.major, 1);
+        assert_eq!(bps[0].address, Some(Address(0x1139)));
+        assert_eq!(bps[0].src_pos.as_ref().unwrap().line, LineNumber::new(3));
+        assert!(!bps[0].temporary);
+
+        // Temporary breakpoint
+        let results = parse_mi(
+            r#"^done,bkpt={number="4",type="breakpoint",disp="del",enabled="y",addr="0x0000000000001139",func="main",file="main.c",fullname="/tmp/main.c",line="3",thread-groups=["i1"],times="0",original-location="-source /tmp/main.c -line 3"}"#,
+        );
+        let bps = BreakPoint::all_from_json(&results["bkpt"]).unwrap();
+        assert!(bps[0].temporary);
+
+        // gdb 8 - 12: multiple locations are listed as additional tuples after the breakpoint
+        let results = parse_mi(
+            r#"^done,bkpt={number="2",type="breakpoint",disp="keep",enabled="y",addr="<MULTIPLE>",times="0",original-location="foo"},{number="2.1",enabled="y",addr="0x0000000000001139",func="foo<int>()",file="main.cpp",fullname="/tmp/main.cpp",line="3",thread-groups=["i1"]},{number="2.2",enabled="n",addr="0x0000000000001149",func="foo<char>()",file="main.cpp",fullname="/tmp/main.cpp",line="3",thread-groups=["i1"]}"#,
+        );
+        let bps = BreakPoint::all_from_json(&results["bkpt"]).unwrap();
+        assert_eq!(bps.len(), 3);
+        assert_eq!(bps[0].address, None);
+        assert_eq!(bps[2].number.minor, Some(2));
+        assert!(!bps[2].enabled);
+
+        // gdb 10: pending breakpoint
+        let results = parse_mi(
+            r#"^done,bkpt={number="3",type="breakpoint",disp="keep",enabled="y",addr="<PENDING>",pending="libfoo.so:bar",times="0",original-location="libfoo.so:bar"}"#,
+        );
+        let bps = BreakPoint::all_from_json(&results["bkpt"]).unwrap();
+        assert_eq!(bps[0].address, None);
+        assert!(bps[0].src_pos.is_none());
+        assert_eq!(bps[0].original_location, Some("libfoo.so:bar".to_owned()));
+
+        // gdb 13+: locations are nested in the breakpoint
+        let results = parse_mi(
+            r#"=breakpoint-modified,bkpt={number="2",type="breakpoint",disp="keep",enabled="y",addr="<MULTIPLE>",times="1",original-location="foo",locations=[{number="2.1",enabled="y",addr="0x0000000000001139",func="foo<int>()",file="main.cpp",fullname="/tmp/main.cpp",line="3",thread-groups=["i1"]},{number="2.2",enabled="n",addr="0x0000000000001149",func="foo<char>()",file="main.cpp",fullname="/tmp/main.cpp",line="3",thread-groups=["i1"]}]}"#,
+        );
+        let bps = BreakPoint::all_from_json(&results["bkpt"]).unwrap();
+        assert_eq!(bps.len(), 3);
+        assert_eq!(bps[0].hit_count, 1);
+        assert_eq!(
+            bps.iter().map(|bp| bp.number.to_string()).collect::<Vec<_>>(),
+            vec!["2", "2.1", "2.2"]
+        );
+        assert_eq!(bps[1].address, Some(Address(0x1139)));
+        assert_eq!(bps[1].function, Some("foo<int>()".to_owned()));
+        assert!(!bps[2].enabled);
+
+        // gdb 13+: -break-info lists the nested locations in the breakpoint table
+        let results = parse_mi(
+            r#"^done,BreakpointTable={nr_rows="1",nr_cols="6",hdr=[{width="7",alignment="-1",col_name="number",colhdr="Num"}],body=[bkpt={number="2",type="breakpoint",disp="keep",enabled="n",addr="<MULTIPLE>",times="0",original-location="foo",locations=[{number="2.1",enabled="y",addr="0x0000000000001139",func="foo<int>()",file="main.cpp",fullname="/tmp/main.cpp",line="3",thread-groups=["i1"]}]}]}"#,
+        );
+        let bps = results["BreakpointTable"]["body"]
+            .members()
+            .map(BreakPoint::all_from_json)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(bps[0].len(), 2);
+        assert_eq!(bps[0][1].number.minor, Some(1));
+    }
+
+    #[test]
+    fn test_active_locations() {
+        let mut set = BreakPointSet::new();
+        let results = parse_mi(
+            r#"=breakpoint-modified,bkpt={number="2",type="breakpoint",disp="keep",enabled="n",addr="<MULTIPLE>",times="0",original-location="foo",locations=[{number="2.1",enabled="y",addr="0x0000000000001139",func="foo<int>()",file="main.cpp",fullname="/tmp/main.cpp",line="3",thread-groups=["i1"]}]}"#,
+        );
+        for bp in BreakPoint::all_from_json(&results["bkpt"]).unwrap() {
+            set.update_breakpoint(bp);
+        }
+        let results = parse_mi(
+            r#"=breakpoint-created,bkpt={number="3",type="breakpoint",disp="keep",enabled="y",addr="<MULTIPLE>",times="0",original-location="bar",locations=[{number="3.1",enabled="y",addr="0x0000000000001200",func="bar()",file="main.cpp",fullname="/tmp/main.cpp",line="9",thread-groups=["i1"]},{number="3.2",enabled="n",addr="0x0000000000001300",func="bar()",file="other.cpp",fullname="/tmp/other.cpp",line="9",thread-groups=["i1"]}]}"#,
+        );
+        for bp in BreakPoint::all_from_json(&results["bkpt"]).unwrap() {
+            set.update_breakpoint(bp);
+        }
+        let mut active = set
+            .active_locations()
+            .map(|bp| bp.number.to_string())
+            .collect::<Vec<_>>();
+        active.sort();
+        assert_eq!(active, vec!["3", "3.1"]);
+    }
+
+    #[test]
+    fn test_malformed_breakpoints() {
+        assert!(BreakPoint::all_from_json(&JsonValue::String("1".to_owned())).is_err());
+        let results = parse_mi(r#"=breakpoint-created,bkpt={type="breakpoint",enabled="y"}"#);
+        assert!(BreakPoint::all_from_json(&results["bkpt"]).is_err());
+        let results = parse_mi(r#"=breakpoint-created,bkpt={number="x",enabled="y"}"#);
+        assert!(BreakPoint::all_from_json(&results["bkpt"]).is_err());
+        let results = parse_mi(r#"=breakpoint-created,bkpt={number="1",enabled="y",line="?"}"#);
+        assert!(BreakPoint::all_from_json(&results["bkpt"]).is_err());
+    }
+
+    #[test]
+    fn test_frames_from_mi() {
+        // gdb 12
+        let results = parse_mi(
+            r#"^done,stack=[frame={level="0",addr="0x0000555555555131",func="main",file="main.c",fullname="/tmp/main.c",line="3",arch="i386:x86-64"},frame={level="1",addr="0x00007ffff7dd0d90",func="__libc_start_call_main",from="/lib/x86_64-linux-gnu/libc.so.6",arch="i386:x86-64"}]"#,
+        );
+        let frames = results["stack"]
+            .members()
+            .map(StackFrame::from_json)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[1].level, 1);
+        assert_eq!(
+            frames[1].library,
+            Some("/lib/x86_64-linux-gnu/libc.so.6".to_owned())
+        );
+        assert!(frames[1].src_pos.is_none());
+
+        // gdb 8: frames without debug information lack the function name
+        let results = parse_mi(r#"^done,stack=[frame={level="0",addr="0x00000000004004d6"}]"#);
+        let frame = StackFrame::from_json(&results["stack"][0]).unwrap();
+        assert_eq!(frame.function, None);
+        assert_eq!(frame.address, Some(Address(0x4004d6)));
+
+        let results = parse_mi(r#"^done,stack=[frame={addr="0x00000000004004d6"}]"#);
+        assert!(StackFrame::from_json(&results["stack"][0]).is_err());
+    }
+
+    #[test]
+    fn test_threads_from_mi() {
+        let results = parse_mi(
+            r#"^done,threads=[{id="2",target-id="Thread 0x7ffff7d85640 (LWP 4243)",name="worker",state="running",core="1"},{id="1",target-id="Thread 0x7ffff7d86740 (LWP 4242)",name="main",frame={level="0",addr="0x0000555555555131",func="main",args=[],file="main.c",fullname="/tmp/main.c",line="3",arch="i386:x86-64"},state="stopped",core="3"}],current-thread-id="1""#,
+        );
+        let threads = results["threads"]
+            .members()
+            .map(Thread::from_json)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(threads[0].state, ThreadState::Running);
+        assert!(threads[0].frame.is_none());
+        assert_eq!(threads[1].name, Some("main".to_owned()));
+        assert_eq!(threads[1].frame.as_ref().unwrap().level, 0);
+    }
+
+    #[test]
+    fn test_breakpoint_kind() {
+        let bp = BreakPoint::from_json(&parse_object(
+            r#"{"number": "4", "type": "acc watchpoint", "enabled": "y", "what": "buf[2]"}"#,
+        ))
+        .unwrap();
+        assert_eq!(bp.kind, BreakPointKind::Watchpoint(WatchMode::Access));
+        assert_eq!(bp.expression, Some("buf[2]".to_owned()));
+        let bp = BreakPoint::from_json(&parse_object(
+            r#"{"number": "1", "type": "breakpoint", "enabled": "n", "what": "main.c:3"}"#,
+        ))
+        .unwrap();
+        assert_eq!(bp.kind, BreakPointKind::Breakpoint);
+        assert_eq!(bp.expression, None);
+    }
+}
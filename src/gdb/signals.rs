@@ -0,0 +1,70 @@
+// Signal names and explanations that ugdb shows to the user.
+
+// Name and description (as printed by gdb) of the standard signals on Linux.
+pub fn signal_description(signal: u32) -> Option<(&'static str, &'static str)> {
+    Some(match signal {
+        1 => ("SIGHUP", "Hangup"),
+        2 => ("SIGINT", "Interrupt"),
+        3 => ("SIGQUIT", "Quit"),
+        4 => ("SIGILL", "Illegal instruction"),
+        5 => ("SIGTRAP", "Trace/breakpoint trap"),
+        6 => ("SIGABRT", "Aborted"),
+        7 => ("SIGBUS", "Bus error"),
+        8 => ("SIGFPE", "Arithmetic exception"),
+        9 => ("SIGKILL", "Killed"),
+        10 => ("SIGUSR1", "User defined signal 1"),
+        11 => ("SIGSEGV", "Segmentation fault"),
+        12 => ("SIGUSR2", "User defined signal 2"),
+        13 => ("SIGPIPE", "Broken pipe"),
+        14 => ("SIGALRM", "Alarm clock"),
+        15 => ("SIGTERM", "Terminated"),
+        24 => ("SIGXCPU", "CPU time limit exceeded"),
+        25 => ("SIGXFSZ", "File size limit exceeded"),
+        31 => ("SIGSYS", "Bad system call"),
+        _ => return None,
+    })
+}
+
+// Signals for which the kernel records the faulting address in the siginfo.
+pub fn is_fault_signal(signal: u32) -> bool {
+    match signal {
+        4 | 7 | 8 | 11 => true,
+        _ => false,
+    }
+}
+
+pub const PTRACE_SCOPE_PATH: &str = "/proc/sys/kernel/yama/ptrace_scope";
+
+// Explain why ptrace refused to attach, depending on the setting of the yama security module
+// (see PTRACE_SCOPE_PATH), if present.
+pub fn ptrace_permission_hint(ptrace_scope: Option<u32>) -> String {
+    match ptrace_scope {
+        Some(1) => "Only descendants of gdb may be traced (kernel.yama.ptrace_scope = 1). \
+                    Run ugdb as root or allow attaching via \
+                    'sudo sysctl kernel.yama.ptrace_scope=0'."
+            .to_owned(),
+        Some(2) => "Only processes with CAP_SYS_PTRACE may attach \
+                    (kernel.yama.ptrace_scope = 2). Run ugdb as root."
+            .to_owned(),
+        Some(scope) if scope >= 3 => format!(
+            "Attaching is disabled until the next reboot (kernel.yama.ptrace_scope = {}).",
+            scope
+        ),
+        _ => "The process may belong to another user. Run ugdb as that user or as root."
+            .to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_ptrace_permission_hint() {
+        assert!(ptrace_permission_hint(Some(1)).contains("ptrace_scope=0"));
+        assert!(ptrace_permission_hint(Some(2)).contains("CAP_SYS_PTRACE"));
+        assert!(ptrace_permission_hint(Some(3)).contains("reboot"));
+        assert!(ptrace_permission_hint(None).contains("another user"));
+        assert!(ptrace_permission_hint(Some(0)).contains("another user"));
+    }
+}
@@ -108,7 +108,6 @@ mod test {
             "array" => "0x7fffffffe018"
         };
         let r = parse_gdb_value(testcase).unwrap();
-        println!("{}", r.pretty(2));
         assert_eq!(r, result_obj);
     }
 
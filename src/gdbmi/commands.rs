@@ -17,13 +17,63 @@ pub enum DisassembleMode {
     MixedSourceAndDisassemblyWithRawOpcodes = 5,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ValuePrintMode {
+    NoValues,
+    AllValues,
+    SimpleValues, // Only values of simple (non-aggregate) types
+}
+
+impl ValuePrintMode {
+    fn as_option(self) -> OsString {
+        OsString::from(match self {
+            ValuePrintMode::NoValues => "--no-values",
+            ValuePrintMode::AllValues => "--all-values",
+            ValuePrintMode::SimpleValues => "--simple-values",
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RegisterFormat {
+    Hexadecimal,
+    Octal,
+    Binary,
+    Decimal,
+    Raw,
+    Natural,
+}
+
+impl RegisterFormat {
+    fn as_option(self) -> OsString {
+        OsString::from(match self {
+            RegisterFormat::Hexadecimal => "x",
+            RegisterFormat::Octal => "o",
+            RegisterFormat::Binary => "t",
+            RegisterFormat::Decimal => "d",
+            RegisterFormat::Raw => "r",
+            RegisterFormat::Natural => "N",
+        })
+    }
+}
+
+#[derive(Clone, Copy)]
 pub enum BreakPointLocation<'a> {
     Address(usize),
     Function(&'a Path, &'a str),
     Line(&'a Path, usize),
+    // Any location gdb understands, e.g., "main.c:3", "*0x4004d6" or "-source main.c -line 3"
+    Spec(&'a str),
 }
 
-#[derive(Copy, Clone, Hash, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WatchMode {
+    Write,
+    Read,
+    Access, // Read or write
+}
+
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub struct BreakPointNumber {
     pub major: usize,
     pub minor: Option<usize>,
@@ -65,6 +115,22 @@ impl fmt::Display for BreakPointNumber {
     }
 }
 
+// Location argument of commands that are forwarded to the cli, e.g., until or jump. gdb only passes
+// the first argument on to the cli command, so the location has to be a single (quoted) linespec.
+fn location_argument(location: BreakPointLocation) -> OsString {
+    let linespec = match location {
+        BreakPointLocation::Address(addr) => format!("*0x{:x}", addr),
+        BreakPointLocation::Function(path, func_name) => {
+            format!("'{}':{}", path.to_string_lossy(), func_name)
+        }
+        BreakPointLocation::Line(path, line_number) => {
+            format!("'{}':{}", path.to_string_lossy(), line_number)
+        }
+        BreakPointLocation::Spec(spec) => spec.to_owned(),
+    };
+    escape_command(&linespec).into()
+}
+
 fn escape_command(input: &str) -> String {
     let mut output = String::new();
     output.push('\"');
@@ -103,6 +169,21 @@ impl MiCommand {
         write!(sink, "\n")?;
         Ok(())
     }
+
+    // Execute the command in the context of the given thread instead of the selected thread.
+    pub fn on_thread(mut self, thread_id: u64) -> MiCommand {
+        self.options.insert(0, OsString::from(thread_id.to_string()));
+        self.options.insert(0, OsString::from("--thread"));
+        self
+    }
+
+    // Execute an exec-* command backwards. This requires a target that supports reverse
+    // execution, e.g., rr or a process recorded via "record".
+    pub fn reverse(mut self) -> MiCommand {
+        self.options.push(OsString::from("--reverse"));
+        self
+    }
+
     pub fn interpreter_exec<S1: Into<OsString>, S2: Into<OsString>>(
         interpreter: S1,
         command: S2,
@@ -155,6 +236,58 @@ impl MiCommand {
         }
     }
 
+    pub fn data_list_register_names() -> MiCommand {
+        MiCommand {
+            operation: "data-list-register-names",
+            options: Vec::new(),
+            parameters: Vec::new(),
+        }
+    }
+
+    // Values of all registers
+    pub fn data_list_register_values(format: RegisterFormat) -> MiCommand {
+        MiCommand {
+            operation: "data-list-register-values",
+            options: vec![format.as_option()],
+            parameters: Vec::new(),
+        }
+    }
+
+    // Registers that changed since the last stop
+    pub fn data_list_changed_registers() -> MiCommand {
+        MiCommand {
+            operation: "data-list-changed-registers",
+            options: Vec::new(),
+            parameters: Vec::new(),
+        }
+    }
+
+    pub fn data_read_memory_bytes(address: usize, count: usize) -> MiCommand {
+        MiCommand {
+            operation: "data-read-memory-bytes",
+            options: vec![
+                OsString::from(format!("0x{:x}", address)),
+                OsString::from(count.to_string()),
+            ],
+            parameters: Vec::new(),
+        }
+    }
+
+    pub fn data_write_memory_bytes(address: usize, bytes: &[u8]) -> MiCommand {
+        let contents = bytes
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<String>();
+        MiCommand {
+            operation: "data-write-memory-bytes",
+            options: vec![
+                OsString::from(format!("0x{:x}", address)),
+                OsString::from(contents),
+            ],
+            parameters: Vec::new(),
+        }
+    }
+
     pub fn data_evaluate_expression(expression: String) -> MiCommand {
         MiCommand {
             operation: "data-evaluate-expression",
@@ -184,7 +317,34 @@ impl MiCommand {
                     OsString::from("--line"),
                     OsString::from(format!("{}", line_number)),
                 ],
+                // Create a pending breakpoint if the location cannot be resolved (yet), e.g.,
+                // because it is located in a library that has not been loaded.
+                BreakPointLocation::Spec(_) => vec![OsString::from("-f")],
+            },
+            parameters: match location {
+                BreakPointLocation::Spec(spec) => vec![escape_command(spec).into()],
+                _ => Vec::new(),
             },
+        }
+    }
+
+    // The breakpoint is deleted after it has been hit once.
+    pub fn insert_temporary_breakpoint(location: BreakPointLocation) -> MiCommand {
+        let mut command = Self::insert_breakpoint(location);
+        command.options.insert(0, OsString::from("-t"));
+        command
+    }
+
+    pub fn insert_watchpoint(expression: &str, mode: WatchMode) -> MiCommand {
+        let mut options = match mode {
+            WatchMode::Write => Vec::new(),
+            WatchMode::Read => vec![OsString::from("-r")],
+            WatchMode::Access => vec![OsString::from("-a")],
+        };
+        options.push(escape_command(expression).into());
+        MiCommand {
+            operation: "break-watch",
+            options: options,
             parameters: Vec::new(),
         }
     }
@@ -206,6 +366,65 @@ impl MiCommand {
         }
     }
 
+    pub fn break_enable<I: Iterator<Item = BreakPointNumber>>(breakpoint_numbers: I) -> MiCommand {
+        MiCommand {
+            operation: "break-enable",
+            options: breakpoint_numbers.map(|n| n.to_string().into()).collect(),
+            parameters: Vec::new(),
+        }
+    }
+
+    pub fn break_disable<I: Iterator<Item = BreakPointNumber>>(
+        breakpoint_numbers: I,
+    ) -> MiCommand {
+        MiCommand {
+            operation: "break-disable",
+            options: breakpoint_numbers.map(|n| n.to_string().into()).collect(),
+            parameters: Vec::new(),
+        }
+    }
+
+    // Passing no condition makes the breakpoint unconditional.
+    pub fn break_condition(number: BreakPointNumber, condition: Option<&str>) -> MiCommand {
+        let mut options = vec![OsString::from(number.to_string())];
+        // The condition is the (unquoted) rest of the line.
+        if let Some(condition) = condition {
+            options.push(condition.into());
+        }
+        MiCommand {
+            operation: "break-condition",
+            options: options,
+            parameters: Vec::new(),
+        }
+    }
+
+    pub fn break_after(number: BreakPointNumber, ignore_count: usize) -> MiCommand {
+        MiCommand {
+            operation: "break-after",
+            options: vec![number.to_string().into(), ignore_count.to_string().into()],
+            parameters: Vec::new(),
+        }
+    }
+
+    // Passing no commands clears the command list of the breakpoint.
+    pub fn break_commands<S: AsRef<str>>(number: BreakPointNumber, commands: &[S]) -> MiCommand {
+        let mut options = vec![OsString::from(number.to_string())];
+        options.extend(commands.iter().map(|c| escape_command(c.as_ref()).into()));
+        MiCommand {
+            operation: "break-commands",
+            options: options,
+            parameters: Vec::new(),
+        }
+    }
+
+    pub fn break_info(number: BreakPointNumber) -> MiCommand {
+        MiCommand {
+            operation: "break-info",
+            options: vec![number.to_string().into()],
+            parameters: Vec::new(),
+        }
+    }
+
     pub fn environment_pwd() -> MiCommand {
         MiCommand {
             operation: "environment-pwd",
@@ -214,6 +433,80 @@ impl MiCommand {
         }
     }
 
+    pub fn exec_run() -> MiCommand {
+        MiCommand {
+            operation: "exec-run",
+            options: Vec::new(),
+            parameters: Vec::new(),
+        }
+    }
+
+    pub fn exec_continue() -> MiCommand {
+        MiCommand {
+            operation: "exec-continue",
+            options: Vec::new(),
+            parameters: Vec::new(),
+        }
+    }
+
+    pub fn exec_next() -> MiCommand {
+        MiCommand {
+            operation: "exec-next",
+            options: Vec::new(),
+            parameters: Vec::new(),
+        }
+    }
+
+    pub fn exec_step() -> MiCommand {
+        MiCommand {
+            operation: "exec-step",
+            options: Vec::new(),
+            parameters: Vec::new(),
+        }
+    }
+
+    pub fn exec_next_instruction() -> MiCommand {
+        MiCommand {
+            operation: "exec-next-instruction",
+            options: Vec::new(),
+            parameters: Vec::new(),
+        }
+    }
+
+    pub fn exec_step_instruction() -> MiCommand {
+        MiCommand {
+            operation: "exec-step-instruction",
+            options: Vec::new(),
+            parameters: Vec::new(),
+        }
+    }
+
+    pub fn exec_finish() -> MiCommand {
+        MiCommand {
+            operation: "exec-finish",
+            options: Vec::new(),
+            parameters: Vec::new(),
+        }
+    }
+
+    // Continue until the location is reached or the current frame returns.
+    pub fn exec_until(location: BreakPointLocation) -> MiCommand {
+        MiCommand {
+            operation: "exec-until",
+            options: vec![location_argument(location)],
+            parameters: Vec::new(),
+        }
+    }
+
+    // Resume execution at the location.
+    pub fn exec_jump(location: BreakPointLocation) -> MiCommand {
+        MiCommand {
+            operation: "exec-jump",
+            options: vec![location_argument(location)],
+            parameters: Vec::new(),
+        }
+    }
+
     // Be aware: This does not seem to always interrupt execution.
     // Use gdb.interrupt_execution instead.
     pub fn exec_interrupt() -> MiCommand {
@@ -271,6 +564,44 @@ impl MiCommand {
         }
     }
 
+    // Frames are selected by an inclusive range of levels (or all, if no range is given).
+    pub fn stack_list_frames(levels: Option<(u64, u64)>) -> MiCommand {
+        MiCommand {
+            operation: "stack-list-frames",
+            options: if let Some((low, high)) = levels {
+                vec![low.to_string().into(), high.to_string().into()]
+            } else {
+                vec![]
+            },
+            parameters: Vec::new(),
+        }
+    }
+
+    pub fn stack_list_arguments(
+        print_mode: ValuePrintMode,
+        levels: Option<(u64, u64)>,
+    ) -> MiCommand {
+        let mut options = vec![print_mode.as_option()];
+        if let Some((low, high)) = levels {
+            options.push(low.to_string().into());
+            options.push(high.to_string().into());
+        }
+        MiCommand {
+            operation: "stack-list-arguments",
+            options: options,
+            parameters: Vec::new(),
+        }
+    }
+
+    // Arguments and locals of the selected frame
+    pub fn stack_list_variables(print_mode: ValuePrintMode) -> MiCommand {
+        MiCommand {
+            operation: "stack-list-variables",
+            options: vec![print_mode.as_option()],
+            parameters: Vec::new(),
+        }
+    }
+
     pub fn thread_info(thread_id: Option<u64>) -> MiCommand {
         MiCommand {
             operation: "thread-info",
@@ -283,6 +614,14 @@ impl MiCommand {
         }
     }
 
+    pub fn thread_select(thread_id: u64) -> MiCommand {
+        MiCommand {
+            operation: "thread-select",
+            options: vec![thread_id.to_string().into()],
+            parameters: Vec::new(),
+        }
+    }
+
     pub fn file_exec_and_symbols(file: &Path) -> MiCommand {
         MiCommand {
             operation: "file-exec-and-symbols",
@@ -303,6 +642,42 @@ impl MiCommand {
         }
     }
 
+    pub fn file_list_shared_libraries() -> MiCommand {
+        MiCommand {
+            operation: "file-list-shared-libraries",
+            options: Vec::new(),
+            parameters: Vec::new(),
+        }
+    }
+
+    // Connect to gdbserver or another remote stub, e.g., at "localhost:1234" or "/dev/ttyS0".
+    pub fn target_select_remote(address: &str, extended: bool) -> MiCommand {
+        MiCommand {
+            operation: "target-select",
+            options: vec![
+                OsString::from(if extended { "extended-remote" } else { "remote" }),
+                OsString::from(address),
+            ],
+            parameters: Vec::new(),
+        }
+    }
+
+    pub fn target_attach(pid: u32) -> MiCommand {
+        MiCommand {
+            operation: "target-attach",
+            options: vec![pid.to_string().into()],
+            parameters: Vec::new(),
+        }
+    }
+
+    pub fn target_detach() -> MiCommand {
+        MiCommand {
+            operation: "target-detach",
+            options: Vec::new(),
+            parameters: Vec::new(),
+        }
+    }
+
     pub fn list_thread_groups(list_all_available: bool, thread_group_ids: &[u32]) -> MiCommand {
         MiCommand {
             operation: "list-thread-groups",
@@ -318,3 +693,95 @@ impl MiCommand {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn serialize(command: MiCommand) -> String {
+        let mut buffer = Vec::new();
+        command
+            .write_interpreter_string(&mut buffer, 7)
+            .expect("write command");
+        String::from_utf8(buffer).expect("utf8 command")
+    }
+
+    #[test]
+    fn test_insert_temporary_breakpoint() {
+        assert_eq!(
+            serialize(MiCommand::insert_temporary_breakpoint(
+                BreakPointLocation::Address(0x1139)
+            )),
+            "7-break-insert -t *0x1139\n"
+        );
+        assert_eq!(
+            serialize(MiCommand::insert_temporary_breakpoint(
+                BreakPointLocation::Line(Path::new("/tmp/main.c"), 3)
+            )),
+            "7-break-insert -t --source /tmp/main.c --line 3\n"
+        );
+        assert_eq!(
+            serialize(MiCommand::insert_temporary_breakpoint(
+                BreakPointLocation::Spec("main.c:3")
+            )),
+            "7-break-insert -t -f -- \"main.c:3\"\n"
+        );
+    }
+
+    // gdb only forwards the first argument of exec-until and exec-jump to the cli.
+    fn assert_single_argument(command: &MiCommand, linespec: &str) {
+        assert_eq!(command.options, vec![OsString::from(escape_command(linespec))]);
+        assert!(command.parameters.is_empty());
+    }
+
+    #[test]
+    fn test_exec_until() {
+        let command = MiCommand::exec_until(BreakPointLocation::Address(0x1139));
+        assert_single_argument(&command, "*0x1139");
+        assert_eq!(serialize(command), "7-exec-until \"*0x1139\"\n");
+
+        let command = MiCommand::exec_until(BreakPointLocation::Line(
+            Path::new("/tmp/my project/main.c"),
+            3,
+        ));
+        assert_single_argument(&command, "'/tmp/my project/main.c':3");
+        assert_eq!(
+            serialize(command),
+            "7-exec-until \"'/tmp/my project/main.c':3\"\n"
+        );
+
+        let command = MiCommand::exec_until(BreakPointLocation::Function(
+            Path::new("/tmp/main.c"),
+            "foo",
+        ));
+        assert_single_argument(&command, "'/tmp/main.c':foo");
+        assert_eq!(serialize(command), "7-exec-until \"'/tmp/main.c':foo\"\n");
+    }
+
+    #[test]
+    fn test_exec_jump() {
+        let command = MiCommand::exec_jump(BreakPointLocation::Address(0x1139));
+        assert_single_argument(&command, "*0x1139");
+        assert_eq!(serialize(command), "7-exec-jump \"*0x1139\"\n");
+
+        let command = MiCommand::exec_jump(BreakPointLocation::Line(Path::new("/tmp/main.c"), 42));
+        assert_single_argument(&command, "'/tmp/main.c':42");
+        assert_eq!(serialize(command), "7-exec-jump \"'/tmp/main.c':42\"\n");
+
+        let command = MiCommand::exec_jump(BreakPointLocation::Spec("main.c:42"));
+        assert_single_argument(&command, "main.c:42");
+    }
+
+    #[test]
+    fn test_exec_run_and_instruction_steps() {
+        assert_eq!(serialize(MiCommand::exec_run()), "7-exec-run\n");
+        assert_eq!(
+            serialize(MiCommand::exec_next_instruction()),
+            "7-exec-next-instruction\n"
+        );
+        assert_eq!(
+            serialize(MiCommand::exec_step_instruction()),
+            "7-exec-step-instruction\n"
+        );
+    }
+}
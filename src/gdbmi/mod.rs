@@ -2,21 +2,37 @@ pub mod commands;
 pub mod output;
 
 use log::info;
+use std::collections::{HashMap, HashSet};
 use std::ffi::OsString;
 use std::path::PathBuf;
 use std::process::{Child, ChildStdin, Command, Stdio};
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::{mpsc, Arc};
+use std::sync::{mpsc, Arc, Condvar, Mutex};
 use std::thread;
+use std::time::{Duration, Instant};
 
-type Token = u64;
+pub type Token = u64;
 
 pub struct GDB {
     pub process: Child,
     stdin: ChildStdin,
     is_running: Arc<AtomicBool>,
+    // In non-stop mode threads are stopped and resumed individually and gdb accepts commands
+    // while (some) threads are running.
+    non_stop: bool,
+    // Results of the most recent stopped record
+    last_stop: Arc<Mutex<Option<output::Object>>>,
+    // Notified (with last_stop locked) when the program stops
+    stopped: Arc<Condvar>,
+    // Set once gdb confirmed that it is about to exit (e.g., because of -gdb-exit or quit).
+    quit: Arc<AtomicBool>,
     result_output: mpsc::Receiver<output::ResultRecord>,
     current_command_token: Token,
+    // Tokens of commands issued via execute_async. Their results are passed to the
+    // ResultRecordSink by the output thread instead of being returned from execute.
+    async_tokens: Arc<Mutex<HashSet<Token>>>,
+    // Pending asynchronous commands and their deadline (if any)
+    pending: HashMap<Token, Option<Instant>>,
     //outputThread: thread::Thread,
 }
 
@@ -24,12 +40,23 @@ pub trait OutOfBandRecordSink: std::marker::Send {
     fn send(&self, output::OutOfBandRecord);
 }
 
+pub trait ResultRecordSink: std::marker::Send {
+    fn send(&self, output::ResultRecord);
+}
+
+#[derive(Debug)]
+pub enum AsyncResult {
+    Done(output::ResultRecord),
+    TimedOut,
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum ExecuteError {
     Busy,
     Quit,
 }
 
+#[derive(Clone)]
 pub struct GDBBuilder {
     gdb_path: PathBuf,
     opt_nh: bool,
@@ -45,6 +72,8 @@ pub struct GDBBuilder {
     opt_args: Vec<OsString>,
     opt_program: Option<PathBuf>,
     opt_tty: Option<PathBuf>,
+    opt_non_stop: bool,
+    opt_rr_trace: Option<PathBuf>,
 }
 impl GDBBuilder {
     pub fn new(gdb: PathBuf) -> Self {
@@ -63,6 +92,8 @@ impl GDBBuilder {
             opt_args: Vec::new(),
             opt_program: None,
             opt_tty: None,
+            opt_non_stop: false,
+            opt_rr_trace: None,
         }
     }
 
@@ -118,9 +149,22 @@ impl GDBBuilder {
         self.opt_tty = Some(tty);
         self
     }
-    pub fn try_spawn<S>(self, oob_sink: S) -> Result<GDB, ::std::io::Error>
+    pub fn non_stop(mut self) -> Self {
+        self.opt_non_stop = true;
+        self
+    }
+    pub fn rr_replay(mut self, trace_dir: PathBuf) -> Self {
+        self.opt_rr_trace = Some(trace_dir);
+        self
+    }
+    pub fn try_spawn<S, R>(
+        self,
+        oob_sink: S,
+        async_result_sink: R,
+    ) -> Result<GDB, ::std::io::Error>
     where
         S: OutOfBandRecordSink + 'static,
+        R: ResultRecordSink + 'static,
     {
         let mut args = Vec::<OsString>::new();
         if self.opt_nh {
@@ -164,6 +208,11 @@ impl GDBBuilder {
             args.push("--tty=".into());
             args.last_mut().unwrap().push(&tty);
         }
+        if self.opt_non_stop {
+            // Both have to be set before the program is started.
+            args.push("--init-eval-command=set mi-async on".into());
+            args.push("--init-eval-command=set non-stop on".into());
+        }
         if !self.opt_args.is_empty() {
             args.push("--args".into());
             args.push(self.opt_program.unwrap().into());
@@ -174,7 +223,23 @@ impl GDBBuilder {
             args.push(program.into());
         }
 
-        let mut child = Command::new(self.gdb_path)
+        let mut command = if let Some(trace_dir) = self.opt_rr_trace {
+            // rr starts gdb connected to its gdbserver and passes the options after "--" to gdb.
+            // Output of the replayed program would end up in the mi output of gdb, so it is
+            // suppressed.
+            let mut command = Command::new("rr");
+            command
+                .arg("replay")
+                .arg("--no-redirect-output")
+                .arg("--debugger")
+                .arg(self.gdb_path)
+                .arg(trace_dir)
+                .arg("--");
+            command
+        } else {
+            Command::new(self.gdb_path)
+        };
+        let mut child = command
             .arg("--interpreter=mi")
             .args(args)
             .stdin(Stdio::piped())
@@ -184,19 +249,43 @@ impl GDBBuilder {
         let stdout = child.stdout.take().expect("take stdout");
         let is_running = Arc::new(AtomicBool::new(false));
         let is_running_for_thread = is_running.clone();
+        let last_stop = Arc::new(Mutex::new(None));
+        let last_stop_for_thread = last_stop.clone();
+        let stopped = Arc::new(Condvar::new());
+        let stopped_for_thread = stopped.clone();
+        let quit = Arc::new(AtomicBool::new(false));
+        let quit_for_thread = quit.clone();
         let (result_input, result_output) = mpsc::channel();
+        let async_tokens = Arc::new(Mutex::new(HashSet::new()));
+        let async_tokens_for_thread = async_tokens.clone();
         /*let outputThread = */
         thread::Builder::new()
             .name("gdbmi parser".to_owned())
             .spawn(move || {
-                output::process_output(stdout, result_input, oob_sink, is_running_for_thread);
+                output::process_output(
+                    stdout,
+                    result_input,
+                    async_result_sink,
+                    async_tokens_for_thread,
+                    oob_sink,
+                    is_running_for_thread,
+                    last_stop_for_thread,
+                    stopped_for_thread,
+                    quit_for_thread,
+                );
             })?;
         let gdb = GDB {
             process: child,
             stdin: stdin,
             is_running: is_running,
+            non_stop: self.opt_non_stop,
+            last_stop: last_stop,
+            stopped: stopped,
+            quit: quit,
             result_output: result_output,
             current_command_token: 0,
+            async_tokens: async_tokens,
+            pending: HashMap::new(),
             //outputThread: outputThread,
         };
         Ok(gdb)
@@ -213,6 +302,45 @@ impl GDB {
     pub fn is_running(&self) -> bool {
         self.is_running.load(Ordering::SeqCst)
     }
+
+    pub fn is_non_stop(&self) -> bool {
+        self.non_stop
+    }
+
+    // In all-stop mode gdb does not accept commands while the program is running.
+    pub fn accepts_commands(&self) -> bool {
+        self.non_stop || !self.is_running()
+    }
+
+    pub fn last_stop(&self) -> Option<output::Object> {
+        self.last_stop.lock().expect("lock last stop").clone()
+    }
+
+    // Block until the program has stopped (e.g., after interrupt_execution). Returns false if it is
+    // still running after the timeout.
+    pub fn wait_until_stopped(&self, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        let mut last_stop = self.last_stop.lock().expect("lock last stop");
+        while self.is_running() {
+            let now = Instant::now();
+            if now >= deadline {
+                return false;
+            }
+            last_stop = self
+                .stopped
+                .wait_timeout(last_stop, deadline - now)
+                .expect("wait for stop")
+                .0;
+        }
+        true
+    }
+
+    // Whether gdb announced that it exits. If gdb terminates without having quit, it crashed or
+    // was killed.
+    pub fn has_quit(&self) -> bool {
+        self.quit.load(Ordering::SeqCst)
+    }
+
     pub fn get_usable_token(&mut self) -> Token {
         self.current_command_token = self.current_command_token.wrapping_add(1);
         self.current_command_token
@@ -222,15 +350,16 @@ impl GDB {
         &mut self,
         command: C,
     ) -> Result<output::ResultRecord, ExecuteError> {
-        if self.is_running() {
+        if !self.accepts_commands() {
             return Err(ExecuteError::Busy);
         }
         let command_token = self.get_usable_token();
 
+        // Writing only fails if gdb is gone.
         command
             .borrow()
             .write_interpreter_string(&mut self.stdin, command_token)
-            .expect("write interpreter command");
+            .map_err(|_| ExecuteError::Quit)?;
         loop {
             match self.result_output.recv() {
                 Ok(record) => match record.token {
@@ -245,13 +374,102 @@ impl GDB {
         }
     }
 
+    // Issue a command without waiting for the result. The result record arrives at the
+    // ResultRecordSink and has to be passed to complete in order to match it with the returned
+    // token. If the command does not complete within the timeout, its token is returned by
+    // take_timed_out and the result is dropped.
+    pub fn execute_async<C: std::borrow::Borrow<commands::MiCommand>>(
+        &mut self,
+        command: C,
+        timeout: Option<Duration>,
+    ) -> Result<Token, ExecuteError> {
+        if !self.accepts_commands() {
+            return Err(ExecuteError::Busy);
+        }
+        let command_token = self.get_usable_token();
+        // Register the token before writing the command so that the output thread is aware of it
+        // when the result arrives.
+        self.async_tokens
+            .lock()
+            .expect("lock async tokens")
+            .insert(command_token);
+        if command
+            .borrow()
+            .write_interpreter_string(&mut self.stdin, command_token)
+            .is_err()
+        {
+            // No result will ever arrive for the token.
+            self.async_tokens
+                .lock()
+                .expect("lock async tokens")
+                .remove(&command_token);
+            return Err(ExecuteError::Quit);
+        }
+        self.pending
+            .insert(command_token, timeout.map(|t| Instant::now() + t));
+        Ok(command_token)
+    }
+
+    // Forget about a pending asynchronous command. gdb itself cannot abort a command, so the
+    // result is dropped once it arrives.
+    pub fn cancel(&mut self, token: Token) {
+        self.pending.remove(&token);
+    }
+
+    // Match a result record (received from the ResultRecordSink) with its pending command.
+    // Results of cancelled or timed out commands are dropped.
+    pub fn complete(&mut self, record: output::ResultRecord) -> Option<(Token, AsyncResult)> {
+        match record.token {
+            Some(token) if self.pending.remove(&token).is_some() => {
+                Some((token, AsyncResult::Done(record)))
+            }
+            _ => {
+                info!("Dropping result of cancelled command: {:?}", record);
+                None
+            }
+        }
+    }
+
+    // Time until the next pending command times out.
+    pub fn next_timeout(&self) -> Option<Duration> {
+        let now = Instant::now();
+        self.pending
+            .values()
+            .filter_map(|deadline| *deadline)
+            .min()
+            .map(|deadline| {
+                if deadline > now {
+                    deadline - now
+                } else {
+                    Duration::from_millis(0)
+                }
+            })
+    }
+
+    // Remove all commands that have exceeded their timeout from the pending commands.
+    pub fn take_timed_out(&mut self) -> Vec<Token> {
+        let now = Instant::now();
+        let timed_out = self
+            .pending
+            .iter()
+            .filter(|&(_, deadline)| deadline.map(|d| d <= now).unwrap_or(false))
+            .map(|(&token, _)| token)
+            .collect::<Vec<Token>>();
+        for token in timed_out.iter() {
+            self.pending.remove(token);
+        }
+        timed_out
+    }
+
     pub fn execute_later<C: std::borrow::Borrow<commands::MiCommand>>(&mut self, command: C) {
         let command_token = self.get_usable_token();
-        command
+        if command
             .borrow()
             .write_interpreter_string(&mut self.stdin, command_token)
-            .expect("write interpreter command");
-        let _ = self.result_output.recv();
+            .is_ok()
+        {
+            let _ = self.result_output.recv();
+        }
     }
 
     pub fn is_session_active(&mut self) -> Result<bool, ExecuteError> {
@@ -259,3 +477,149 @@ impl GDB {
         Ok(!res.results["threads"].is_empty())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // A GDB without output thread: Results are passed to complete manually. cat only serves as a
+    // process that accepts the written commands.
+    fn spawn_gdb() -> GDB {
+        let mut process = Command::new("cat")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .spawn()
+            .expect("spawn cat");
+        let stdin = process.stdin.take().expect("take stdin");
+        let (_, result_output) = mpsc::channel();
+        GDB {
+            process: process,
+            stdin: stdin,
+            is_running: Arc::new(AtomicBool::new(false)),
+            non_stop: false,
+            last_stop: Arc::new(Mutex::new(None)),
+            stopped: Arc::new(Condvar::new()),
+            quit: Arc::new(AtomicBool::new(false)),
+            result_output: result_output,
+            current_command_token: 0,
+            async_tokens: Arc::new(Mutex::new(HashSet::new())),
+            pending: HashMap::new(),
+        }
+    }
+
+    fn done(token: Token) -> output::ResultRecord {
+        output::ResultRecord {
+            token: Some(token),
+            class: output::ResultClass::Done,
+            results: output::Object::new(),
+        }
+    }
+
+    fn execute_async(gdb: &mut GDB, timeout: Option<Duration>) -> Token {
+        gdb.execute_async(commands::MiCommand::thread_info(None), timeout)
+            .expect("execute async")
+    }
+
+    #[test]
+    fn test_complete() {
+        let mut gdb = spawn_gdb();
+        let token = execute_async(&mut gdb, None);
+        assert!(gdb.async_tokens.lock().unwrap().contains(&token));
+        match gdb.complete(done(token)) {
+            Some((t, AsyncResult::Done(record))) => {
+                assert_eq!(t, token);
+                assert_eq!(record.class, output::ResultClass::Done);
+            }
+            other => panic!("Unexpected completion: {:?}", other),
+        }
+        // Each result is only delivered once.
+        assert!(gdb.complete(done(token)).is_none());
+        assert!(gdb.complete(done(token + 1)).is_none());
+        let _ = gdb.process.kill();
+    }
+
+    #[test]
+    fn test_cancel() {
+        let mut gdb = spawn_gdb();
+        let cancelled = execute_async(&mut gdb, None);
+        let other = execute_async(&mut gdb, None);
+        gdb.cancel(cancelled);
+        assert!(gdb.complete(done(cancelled)).is_none());
+        assert!(gdb.complete(done(other)).is_some());
+        let _ = gdb.process.kill();
+    }
+
+    #[test]
+    fn test_timeouts() {
+        let mut gdb = spawn_gdb();
+        assert_eq!(gdb.next_timeout(), None);
+        let unlimited = execute_async(&mut gdb, None);
+        assert_eq!(gdb.next_timeout(), None);
+        let late = execute_async(&mut gdb, Some(Duration::from_secs(3600)));
+        let next = gdb.next_timeout().expect("next timeout");
+        assert!(next > Duration::from_secs(3500) && next <= Duration::from_secs(3600));
+        let expired = execute_async(&mut gdb, Some(Duration::from_millis(0)));
+        assert_eq!(gdb.next_timeout(), Some(Duration::from_millis(0)));
+
+        assert_eq!(gdb.take_timed_out(), vec![expired]);
+        assert!(gdb.take_timed_out().is_empty());
+        assert!(gdb.complete(done(expired)).is_none());
+        assert!(gdb.next_timeout().expect("next timeout") > Duration::from_secs(3500));
+        assert!(gdb.complete(done(late)).is_some());
+        assert!(gdb.complete(done(unlimited)).is_some());
+        let _ = gdb.process.kill();
+    }
+
+    #[test]
+    fn test_execute_async_after_quit() {
+        let mut gdb = spawn_gdb();
+        gdb.process.kill().expect("kill cat");
+        gdb.process.wait().expect("wait for cat");
+        assert_eq!(
+            gdb.execute_async(commands::MiCommand::thread_info(None), None),
+            Err(ExecuteError::Quit)
+        );
+        assert!(gdb.async_tokens.lock().unwrap().is_empty());
+        assert!(gdb.pending.is_empty());
+    }
+
+    #[test]
+    fn test_wait_until_stopped() {
+        let mut gdb = spawn_gdb();
+        assert!(gdb.wait_until_stopped(Duration::from_millis(0)));
+
+        gdb.is_running.store(true, Ordering::SeqCst);
+        assert!(!gdb.wait_until_stopped(Duration::from_millis(10)));
+
+        let is_running = gdb.is_running.clone();
+        let last_stop = gdb.last_stop.clone();
+        let stopped = gdb.stopped.clone();
+        let stopper = thread::spawn(move || {
+            output::process_output(
+                "*stopped,reason=\"signal-received\",signal-name=\"SIGINT\"\n".as_bytes(),
+                mpsc::channel().0,
+                DropResults,
+                Arc::new(Mutex::new(HashSet::new())),
+                DropOutOfBand,
+                is_running,
+                last_stop,
+                stopped,
+                Arc::new(AtomicBool::new(false)),
+            );
+        });
+        assert!(gdb.wait_until_stopped(Duration::from_secs(10)));
+        assert_eq!(gdb.last_stop().unwrap()["signal-name"], "SIGINT");
+        stopper.join().unwrap();
+        let _ = gdb.process.kill();
+    }
+
+    struct DropResults;
+    impl ResultRecordSink for DropResults {
+        fn send(&self, _: output::ResultRecord) {}
+    }
+
+    struct DropOutOfBand;
+    impl OutOfBandRecordSink for DropOutOfBand {
+        fn send(&self, _: output::OutOfBandRecord) {}
+    }
+}
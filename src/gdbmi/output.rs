@@ -23,17 +23,27 @@ pub enum BreakPointEvent {
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ThreadEvent {
     Created,
+    GroupAdded,
     GroupStarted,
     Exited,
     GroupExited,
+    GroupRemoved,
     Selected,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LibraryEvent {
+    Loaded,
+    Unloaded,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum AsyncClass {
     Stopped,
+    Running,
     CmdParamChanged,
-    LibraryLoaded,
+    MemoryChanged,
+    Library(LibraryEvent),
     Thread(ThreadEvent),
     BreakPoint(BreakPointEvent),
     Other(String), //?
@@ -74,6 +84,178 @@ pub enum OutOfBandRecord {
     },
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StopReason {
+    BreakpointHit,
+    WatchpointTrigger,
+    ReadWatchpointTrigger,
+    AccessWatchpointTrigger,
+    WatchpointScope,
+    FunctionFinished,
+    LocationReached,
+    EndSteppingRange,
+    SignalReceived,
+    Exited,
+    ExitedNormally,
+    ExitedSignalled,
+    SolibEvent,
+    Fork,
+    Vfork,
+    SyscallEntry,
+    SyscallReturn,
+    Exec,
+    NoHistory,
+    Other(String),
+}
+
+impl StopReason {
+    fn parse(reason: &str) -> Self {
+        match reason {
+            "breakpoint-hit" => StopReason::BreakpointHit,
+            "watchpoint-trigger" => StopReason::WatchpointTrigger,
+            "read-watchpoint-trigger" => StopReason::ReadWatchpointTrigger,
+            "access-watchpoint-trigger" => StopReason::AccessWatchpointTrigger,
+            "watchpoint-scope" => StopReason::WatchpointScope,
+            "function-finished" => StopReason::FunctionFinished,
+            "location-reached" => StopReason::LocationReached,
+            "end-stepping-range" => StopReason::EndSteppingRange,
+            "signal-received" => StopReason::SignalReceived,
+            "exited" => StopReason::Exited,
+            "exited-normally" => StopReason::ExitedNormally,
+            "exited-signalled" => StopReason::ExitedSignalled,
+            "solib-event" => StopReason::SolibEvent,
+            "fork" => StopReason::Fork,
+            "vfork" => StopReason::Vfork,
+            "syscall-entry" => StopReason::SyscallEntry,
+            "syscall-return" => StopReason::SyscallReturn,
+            "exec" => StopReason::Exec,
+            "no-history" => StopReason::NoHistory,
+            other => StopReason::Other(other.to_owned()),
+        }
+    }
+}
+
+// Typed view of the results of a *stopped record.
+#[derive(Debug, Clone)]
+pub struct StopEvent {
+    pub reason: Option<StopReason>, // Not present if gdb was interrupted in some cases
+    pub signal_name: Option<String>,
+    pub signal_meaning: Option<String>,
+    pub exit_code: Option<u32>,
+    pub breakpoint_number: Option<usize>,
+    pub return_value: Option<String>,
+    pub thread_id: Option<u64>,
+    pub frame: Option<Object>,
+}
+
+impl StopEvent {
+    pub fn from_results(results: &Object) -> Self {
+        let string = |key: &str| results[key].as_str().map(|s| s.to_owned());
+        StopEvent {
+            reason: results["reason"].as_str().map(StopReason::parse),
+            signal_name: string("signal-name"),
+            signal_meaning: string("signal-meaning"),
+            // gdb reports the exit code in octal
+            exit_code: results["exit-code"]
+                .as_str()
+                .and_then(|c| u32::from_str_radix(c, 8).ok()),
+            breakpoint_number: results["bkptno"]
+                .as_str()
+                .and_then(|n| n.parse::<usize>().ok()),
+            return_value: string("return-value"),
+            thread_id: results["thread-id"]
+                .as_str()
+                .and_then(|id| id.parse::<u64>().ok()),
+            frame: match results["frame"] {
+                JsonValue::Object(ref frame) => Some(frame.clone()),
+                _ => None,
+            },
+        }
+    }
+
+    pub fn program_exited(&self) -> bool {
+        match self.reason {
+            Some(StopReason::Exited)
+            | Some(StopReason::ExitedNormally)
+            | Some(StopReason::ExitedSignalled) => true,
+            _ => false,
+        }
+    }
+
+    fn location(&self) -> Option<String> {
+        let frame = self.frame.as_ref()?;
+        let function = frame["func"].as_str().unwrap_or("??");
+        Some(
+            match (frame["file"].as_str(), frame["line"].as_str(), frame["addr"].as_str()) {
+                (Some(file), Some(line), _) => format!("{} at {}:{}", function, file, line),
+                (_, _, Some(address)) => format!("{} at {}", function, address),
+                _ => function.to_owned(),
+            },
+        )
+    }
+
+    fn signal(&self) -> String {
+        match (&self.signal_name, &self.signal_meaning) {
+            (&Some(ref name), &Some(ref meaning)) => format!("{}, {}", name, meaning),
+            (&Some(ref name), &None) => name.clone(),
+            (&None, _) => "unknown signal".to_owned(),
+        }
+    }
+
+    // Short description of why the program stopped, e.g., "Breakpoint 1 hit".
+    pub fn summary(&self) -> String {
+        match self.reason {
+            Some(StopReason::BreakpointHit) => match self.breakpoint_number {
+                Some(number) => format!("Breakpoint {} hit", number),
+                None => "Breakpoint hit".to_owned(),
+            },
+            Some(StopReason::WatchpointTrigger)
+            | Some(StopReason::ReadWatchpointTrigger)
+            | Some(StopReason::AccessWatchpointTrigger) => "Watchpoint triggered".to_owned(),
+            Some(StopReason::WatchpointScope) => "Watchpoint went out of scope".to_owned(),
+            Some(StopReason::FunctionFinished) => match self.return_value {
+                Some(ref value) => format!("Function finished, returned {}", value),
+                None => "Function finished".to_owned(),
+            },
+            Some(StopReason::LocationReached) => "Location reached".to_owned(),
+            Some(StopReason::EndSteppingRange) => "Stepped".to_owned(),
+            Some(StopReason::SignalReceived) => format!("Program received signal {}", self.signal()),
+            Some(StopReason::Exited) => {
+                format!("Program exited with code {}", self.exit_code.unwrap_or(0))
+            }
+            Some(StopReason::ExitedNormally) => "Program exited normally".to_owned(),
+            Some(StopReason::ExitedSignalled) => {
+                format!("Program terminated with signal {}", self.signal())
+            }
+            Some(StopReason::SolibEvent) => "Shared library event".to_owned(),
+            Some(StopReason::Fork) => "Program forked".to_owned(),
+            Some(StopReason::Vfork) => "Program vforked".to_owned(),
+            Some(StopReason::SyscallEntry) => "Entered system call".to_owned(),
+            Some(StopReason::SyscallReturn) => "Returned from system call".to_owned(),
+            Some(StopReason::Exec) => "Program executed a new image".to_owned(),
+            Some(StopReason::NoHistory) => "No more reverse-execution history".to_owned(),
+            Some(StopReason::Other(ref reason)) => format!("Stopped ({})", reason),
+            None => "Stopped".to_owned(),
+        }
+    }
+
+    // A one-line description of the stop (including where it happened), similar to what the gdb
+    // cli prints.
+    pub fn description(&self) -> String {
+        let mut description = self.summary();
+        if !self.program_exited() {
+            if let Some(id) = self.thread_id {
+                description.push_str(&format!(" in thread {}", id));
+            }
+            if let Some(location) = self.location() {
+                description.push_str(&format!(": {}", location));
+            }
+        }
+        description.push('.');
+        description
+    }
+}
+
 #[derive(Debug)]
 enum Output {
     Result(ResultRecord),
@@ -83,17 +265,23 @@ enum Output {
 }
 
 use nom::IResult;
+use std::collections::HashSet;
 use std::io::{BufRead, BufReader, Read};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::Sender;
-use std::sync::Arc;
-use OutOfBandRecordSink;
+use std::sync::{Arc, Condvar, Mutex};
+use super::{OutOfBandRecordSink, ResultRecordSink};
 
-pub fn process_output<T: Read, S: OutOfBandRecordSink>(
+pub fn process_output<T: Read, R: ResultRecordSink, S: OutOfBandRecordSink>(
     output: T,
     result_pipe: Sender<ResultRecord>,
+    async_result_pipe: R,
+    async_tokens: Arc<Mutex<HashSet<Token>>>,
     out_of_band_pipe: S,
     is_running: Arc<AtomicBool>,
+    last_stop: Arc<Mutex<Option<Object>>>,
+    stopped: Arc<Condvar>,
+    quit: Arc<AtomicBool>,
 ) {
     let mut reader = BufReader::new(output);
 
@@ -110,6 +298,11 @@ pub fn process_output<T: Read, S: OutOfBandRecordSink>(
                     Ok(r) => r,
                     Err(e) => {
                         error!("PARSING ERROR: {}", e);
+                        // Show the line anyway, it may contain information for the user.
+                        out_of_band_pipe.send(OutOfBandRecord::StreamRecord {
+                            kind: StreamKind::Log,
+                            data: format!("Unexpected gdb output ({}): {}", e, buffer),
+                        });
                         continue;
                     }
                 };
@@ -118,18 +311,42 @@ pub fn process_output<T: Read, S: OutOfBandRecordSink>(
                         match record.class {
                             ResultClass::Running => is_running.store(true, Ordering::SeqCst),
                             //Apparently sometimes gdb first claims to be running, only to then stop again (without notifying the user)...
-                            ResultClass::Error => is_running.store(false, Ordering::SeqCst),
+                            ResultClass::Error => {
+                                let _last_stop = last_stop.lock().expect("lock last stop");
+                                is_running.store(false, Ordering::SeqCst);
+                                stopped.notify_all();
+                            }
+                            ResultClass::Exit => quit.store(true, Ordering::SeqCst),
                             _ => {}
                         }
-                        result_pipe.send(record).expect("send result to pipe");
+                        let is_async = record
+                            .token
+                            .map(|token| {
+                                async_tokens
+                                    .lock()
+                                    .expect("lock async tokens")
+                                    .remove(&token)
+                            })
+                            .unwrap_or(false);
+                        if is_async {
+                            async_result_pipe.send(record);
+                        } else {
+                            result_pipe.send(record).expect("send result to pipe");
+                        }
                     }
                     Output::OutOfBand(record) => {
                         if let OutOfBandRecord::AsyncRecord {
                             class: AsyncClass::Stopped,
+                            ref results,
                             ..
                         } = record
                         {
+                            // Store the stop before announcing it via is_running. Both happen while
+                            // holding the lock so that GDB::wait_until_stopped cannot miss the stop.
+                            let mut last_stop = last_stop.lock().expect("lock last stop");
+                            *last_stop = Some(results.clone());
                             is_running.store(false, Ordering::SeqCst);
+                            stopped.notify_all();
                         }
                         out_of_band_pipe.send(record);
                     }
@@ -150,6 +367,20 @@ pub fn process_output<T: Read, S: OutOfBandRecordSink>(
     }
 }
 
+// Parse the results of a single (result or async) record line, e.g., for checking recorded gdb
+// output.
+pub fn parse_results(line: &str) -> Result<Object, String> {
+    let mut line = line.to_owned();
+    if !line.ends_with('\n') {
+        line.push('\n');
+    }
+    match Output::parse(&line)? {
+        Output::Result(record) => Ok(record.results),
+        Output::OutOfBand(OutOfBandRecord::AsyncRecord { results, .. }) => Ok(results),
+        other => Err(format!("Not a record with results: {:?}", other)),
+    }
+}
+
 impl Output {
     fn parse(line: &str) -> Result<Self, String> {
         match output(line.as_bytes()) {
@@ -299,10 +530,15 @@ named!(
     async_class<AsyncClass>,
     alt!(
         value!(AsyncClass::Stopped, tag!("stopped"))
+            | value!(AsyncClass::Running, tag!("running"))
             | value!(
                 AsyncClass::Thread(ThreadEvent::Created),
                 tag!("thread-created")
             )
+            | value!(
+                AsyncClass::Thread(ThreadEvent::GroupAdded),
+                tag!("thread-group-added")
+            )
             | value!(
                 AsyncClass::Thread(ThreadEvent::GroupStarted),
                 tag!("thread-group-started")
@@ -315,12 +551,24 @@ named!(
                 AsyncClass::Thread(ThreadEvent::GroupExited),
                 tag!("thread-group-exited")
             )
+            | value!(
+                AsyncClass::Thread(ThreadEvent::GroupRemoved),
+                tag!("thread-group-removed")
+            )
             | value!(
                 AsyncClass::Thread(ThreadEvent::Selected),
                 tag!("thread-selected")
             )
             | value!(AsyncClass::CmdParamChanged, tag!("cmd-param-changed"))
-            | value!(AsyncClass::LibraryLoaded, tag!("library-loaded"))
+            | value!(AsyncClass::MemoryChanged, tag!("memory-changed"))
+            | value!(
+                AsyncClass::Library(LibraryEvent::Loaded),
+                tag!("library-loaded")
+            )
+            | value!(
+                AsyncClass::Library(LibraryEvent::Unloaded),
+                tag!("library-unloaded")
+            )
             | value!(
                 AsyncClass::BreakPoint(BreakPointEvent::Created),
                 tag!("breakpoint-created")
@@ -425,4 +673,143 @@ mod test {
     fn test_output() {
         let _ = Output::parse("=library-loaded,ranges=[{}]\n");
     }
+
+    fn parse_notification(line: &str) -> (AsyncClass, Object) {
+        match Output::parse(line) {
+            Ok(Output::OutOfBand(OutOfBandRecord::AsyncRecord {
+                kind: AsyncKind::Notify,
+                class,
+                results,
+                ..
+            })) => (class, results),
+            other => panic!("Failed to parse notification: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_notifications() {
+        let (class, results) = parse_notification(
+            "=library-loaded,id=\"/lib/libm.so.6\",target-name=\"/lib/libm.so.6\",host-name=\"/lib/libm.so.6\",symbols-loaded=\"0\",thread-group=\"i1\",ranges=[{from=\"0x00007ffff7e3e000\",to=\"0x00007ffff7eb9000\"}]\n",
+        );
+        assert_eq!(class, AsyncClass::Library(LibraryEvent::Loaded));
+        assert_eq!(results["ranges"][0]["from"], "0x00007ffff7e3e000");
+        let (class, _) = parse_notification(
+            "=library-unloaded,id=\"/lib/libm.so.6\",target-name=\"/lib/libm.so.6\",host-name=\"/lib/libm.so.6\",thread-group=\"i1\"\n",
+        );
+        assert_eq!(class, AsyncClass::Library(LibraryEvent::Unloaded));
+        let (class, _) = parse_notification("=thread-group-added,id=\"i1\"\n");
+        assert_eq!(class, AsyncClass::Thread(ThreadEvent::GroupAdded));
+        let (class, results) =
+            parse_notification("=thread-group-started,id=\"i1\",pid=\"4242\"\n");
+        assert_eq!(class, AsyncClass::Thread(ThreadEvent::GroupStarted));
+        assert_eq!(results["pid"], "4242");
+        let (class, results) =
+            parse_notification("=thread-group-exited,id=\"i1\",exit-code=\"03\"\n");
+        assert_eq!(class, AsyncClass::Thread(ThreadEvent::GroupExited));
+        assert_eq!(results["exit-code"], "03");
+        let (class, _) = parse_notification("=thread-group-removed,id=\"i2\"\n");
+        assert_eq!(class, AsyncClass::Thread(ThreadEvent::GroupRemoved));
+        let (class, results) = parse_notification(
+            "=memory-changed,thread-group=\"i1\",addr=\"0x00007fffffffe0cc\",len=\"0x4\"\n",
+        );
+        assert_eq!(class, AsyncClass::MemoryChanged);
+        assert_eq!(results["len"], "0x4");
+    }
+
+    fn parse_stop(line: &str) -> StopEvent {
+        match Output::parse(line) {
+            Ok(Output::OutOfBand(OutOfBandRecord::AsyncRecord {
+                class: AsyncClass::Stopped,
+                results,
+                ..
+            })) => StopEvent::from_results(&results),
+            other => panic!("Failed to parse stopped record: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_stop_event() {
+        let stop = parse_stop(
+            "*stopped,reason=\"breakpoint-hit\",disp=\"keep\",bkptno=\"1\",frame={addr=\"0x0000555555555131\",func=\"main\",args=[],file=\"main.c\",fullname=\"/tmp/main.c\",line=\"3\"},thread-id=\"1\",stopped-threads=\"all\",core=\"2\"\n",
+        );
+        assert_eq!(stop.reason, Some(StopReason::BreakpointHit));
+        assert_eq!(stop.breakpoint_number, Some(1));
+        assert_eq!(stop.thread_id, Some(1));
+        assert_eq!(
+            stop.description(),
+            "Breakpoint 1 hit in thread 1: main at main.c:3."
+        );
+
+        let stop = parse_stop(
+            "*stopped,reason=\"signal-received\",signal-name=\"SIGSEGV\",signal-meaning=\"Segmentation fault\",frame={addr=\"0x0000555555555140\",func=\"crash\",args=[]},thread-id=\"1\",stopped-threads=\"all\"\n",
+        );
+        assert_eq!(stop.signal_name, Some("SIGSEGV".to_owned()));
+        assert_eq!(
+            stop.description(),
+            "Program received signal SIGSEGV, Segmentation fault in thread 1: crash at 0x0000555555555140."
+        );
+
+        let stop = parse_stop("*stopped,reason=\"exited\",exit-code=\"012\"\n");
+        assert!(stop.program_exited());
+        assert_eq!(stop.exit_code, Some(10));
+        assert_eq!(stop.description(), "Program exited with code 10.");
+
+        let stop = parse_stop("*stopped,reason=\"exited-normally\"\n");
+        assert!(stop.program_exited());
+        assert_eq!(stop.description(), "Program exited normally.");
+    }
+
+    #[test]
+    fn test_running() {
+        match Output::parse("*running,thread-id=\"all\"\n") {
+            Ok(Output::OutOfBand(OutOfBandRecord::AsyncRecord { class, results, .. })) => {
+                assert_eq!(class, AsyncClass::Running);
+                assert_eq!(results["thread-id"], "all");
+            }
+            _ => panic!("Failed to parse running record"),
+        }
+    }
+
+    struct ResultSink(::std::sync::mpsc::Sender<ResultRecord>);
+    impl ResultRecordSink for ResultSink {
+        fn send(&self, record: ResultRecord) {
+            self.0.send(record).expect("send async result");
+        }
+    }
+
+    struct IgnoreOutOfBand;
+    impl OutOfBandRecordSink for IgnoreOutOfBand {
+        fn send(&self, _: OutOfBandRecord) {}
+    }
+
+    #[test]
+    fn test_result_routing() {
+        let (result_input, result_output) = ::std::sync::mpsc::channel();
+        let (async_input, async_output) = ::std::sync::mpsc::channel();
+        let async_tokens = Arc::new(Mutex::new([2, 3].iter().cloned().collect::<HashSet<_>>()));
+        process_output(
+            "1^done\n2^done\n(gdb) \n3^error,msg=\"No symbol\"\n^done\n2^done\n".as_bytes(),
+            result_input,
+            ResultSink(async_input),
+            async_tokens.clone(),
+            IgnoreOutOfBand,
+            Arc::new(AtomicBool::new(false)),
+            Arc::new(Mutex::new(None)),
+            Arc::new(Condvar::new()),
+            Arc::new(AtomicBool::new(false)),
+        );
+        // Results of asynchronous commands are passed to the sink exactly once, all others to
+        // the synchronous pipe.
+        let sync_tokens = result_output.iter().map(|r| r.token).collect::<Vec<_>>();
+        assert_eq!(sync_tokens, vec![Some(1), None, Some(2)]);
+        let async_results = async_output
+            .iter()
+            .map(|r| (r.token, r.class))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            async_results,
+            vec![(Some(2), ResultClass::Done), (Some(3), ResultClass::Error)]
+        );
+        assert!(async_tokens.lock().unwrap().is_empty());
+    }
 }
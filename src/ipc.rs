@@ -126,15 +126,14 @@ impl IPCRequest {
                 file, line
             ))),
             Err(BreakpointOperationError::Busy) => {
-                //TODO: we may want to investigate if we can interrupt execution, insert
-                //breakpoint, and resume execution thereafter.
                 Err(IPCError::new("Could not insert breakpoint", "GDB is busy"))
             }
             Err(BreakpointOperationError::ExecutionError(msg)) => {
-                //TODO: we may want to investigate if we can interrupt execution, insert
-                //breakpoint, and resume execution thereafter.
                 Err(IPCError::new("Could not insert breakpoint:", msg))
             }
+            Err(e @ BreakpointOperationError::MalformedResponse(_)) => {
+                Err(IPCError::new("Could not insert breakpoint:", e.to_string()))
+            }
         }
     }
 
@@ -144,12 +143,9 @@ impl IPCRequest {
     ) -> Result<json::JsonValue, IPCError> {
         let result = p
             .gdb
-            .mi
             .execute(MiCommand::environment_pwd())
             .map_err(|e| match e {
                 ExecuteError::Busy => {
-                    //TODO: we may want to investigate if we can interrupt execution, get information
-                    //and resume execution thereafter.
                     IPCError::new("Could not get working directory", "GDB is busy")
                 }
                 ExecuteError::Quit => IPCError::new("Could not get working directory", "GDB quit"),
@@ -241,6 +237,10 @@ fn start_connection(mut connection: UnixStream, request_sink: chan::Sender<IPCRe
 }
 
 impl IPC {
+    pub fn socket_path(&self) -> &Path {
+        &self.socket_path
+    }
+
     pub fn setup() -> ::std::io::Result<Self> {
         let (request_sink, request_source) = chan::async();
 
@@ -0,0 +1,285 @@
+// Named actions and the keys they are bound to.
+//
+// Every user-facing key of the containers (and of container selection) is referred to by an
+// action name such as "srcview.toggle_breakpoint". The bindings can be changed in the [keys]
+// section of the configuration file, where each action is mapped to a key or a list of
+// alternative keys:
+//
+//   [keys]
+//   "container.enter_select_mode" = "C-a"
+//   "srcview.toggle_breakpoint" = ["Space", "b"]
+//
+// Each binding is a single key (possibly with a modifier, e.g. "C-a"). Key sequences such as
+// "g g" are not supported.
+use std::collections::HashMap;
+use unsegen::input::{Input, Key};
+
+macro_rules! actions {
+    ($($variant:ident => $name:expr, $description:expr, [$($key:expr),*];)*) => {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+        pub enum Action {
+            $($variant,)*
+        }
+
+        impl Action {
+            pub const ALL: &'static [Action] = &[$(Action::$variant,)*];
+
+            pub fn name(self) -> &'static str {
+                match self {
+                    $(Action::$variant => $name,)*
+                }
+            }
+
+            pub fn description(self) -> &'static str {
+                match self {
+                    $(Action::$variant => $description,)*
+                }
+            }
+
+            fn default_keys(self) -> Vec<Key> {
+                match self {
+                    $(Action::$variant => vec![$($key),*],)*
+                }
+            }
+        }
+    };
+}
+
+actions! {
+    EnterContainerSelect => "container.enter_select_mode", "Enter container selection (press twice in focused mode)", [Key::Esc];
+    LeaveContainerSelect => "container.leave_select_mode", "Interact with the selected container", [Key::Char('\n')];
+    ContainerUp => "container.up", "Select the container above", [Key::Char('k'), Key::Up];
+    ContainerDown => "container.down", "Select the container below", [Key::Char('j'), Key::Down];
+    ContainerLeft => "container.left", "Select the container to the left", [Key::Char('h'), Key::Left];
+    ContainerRight => "container.right", "Select the container to the right", [Key::Char('l'), Key::Right];
+    SelectConsole => "container.select_console", "Interact with the console", [Key::Char('i')];
+    SelectExpressionTable => "container.select_expression_table", "Interact with the expression table", [Key::Char('e')];
+    SelectSrcView => "container.select_srcview", "Interact with the source view", [Key::Char('s')];
+    SelectTerminal => "container.select_terminal", "Interact with the terminal", [Key::Char('t')];
+    SelectBreakpoints => "container.select_breakpoints", "Interact with the breakpoint list", [Key::Char('b')];
+    SelectBacktrace => "container.select_backtrace", "Interact with the backtrace", [Key::Char('f')];
+    SelectThreads => "container.select_threads", "Interact with the thread list", [Key::Char('r')];
+    SelectLocals => "container.select_locals", "Interact with the local variables", [Key::Char('v')];
+    SelectRegisters => "container.select_registers", "Interact with the register list", [Key::Char('g')];
+    SelectMemory => "container.select_memory", "Interact with the memory viewer", [Key::Char('m')];
+    SelectLibraries => "container.select_libraries", "Interact with the shared library list", [Key::Char('o')];
+    SelectCoreSummary => "container.select_core_summary", "Interact with the core dump summary", [Key::Char('p')];
+    FocusTerminal => "container.focus_terminal", "Interact with the terminal (focused mode)", [Key::Char('T')];
+    ShowHelp => "container.show_help", "Show this help", [Key::Char('?')];
+    GlobalNext => "global.next", "Step over function calls, regardless of the active container", [Key::F(10)];
+    GlobalStep => "global.step", "Step into function calls, regardless of the active container", [Key::F(11)];
+    GlobalFinish => "global.finish", "Run until the current function returns, regardless of the active container", [Key::F(12)];
+    GlobalContinue => "global.continue", "Continue until a breakpoint is hit, regardless of the active container", [Key::F(5)];
+    GlobalRun => "global.run", "Start the program, regardless of the active container", [Key::F(4)];
+    PromptSubmit => "prompt.submit", "Confirm the input of a container prompt", [Key::Char('\n')];
+    PromptCancel => "prompt.cancel", "Discard the input of a container prompt", [Key::Ctrl('c')];
+    ListUp => "list.up", "Select the previous entry of a list", [Key::Up, Key::Char('k')];
+    ListDown => "list.down", "Select the next entry of a list", [Key::Down, Key::Char('j')];
+    ListToBeginning => "list.to_beginning", "Select the first entry of a list", [Key::Home];
+    ListToEnd => "list.to_end", "Select the last entry of a list", [Key::End];
+    CodeWindowToggleMode => "codewindow.toggle_mode", "Toggle between source, assembly and side-by-side view", [Key::Char('d')];
+    CodeWindowStackUp => "codewindow.stack_up", "Select the calling stack frame", [Key::PageUp];
+    CodeWindowStackDown => "codewindow.stack_down", "Select the called stack frame", [Key::PageDown];
+    CodeWindowNext => "codewindow.next", "Step over function calls (by instruction in assembly mode)", [Key::Char('n')];
+    CodeWindowStep => "codewindow.step", "Step into function calls (by instruction in assembly mode)", [Key::Char('s')];
+    CodeWindowFinish => "codewindow.finish", "Run until the current function returns", [Key::Char('f')];
+    CodeWindowContinue => "codewindow.continue", "Continue until a breakpoint is hit", [Key::Char('c')];
+    CodeWindowRun => "codewindow.run", "Start the program", [Key::Char('r')];
+    CodeWindowReverseNext => "codewindow.reverse_next", "Step backwards over function calls", [Key::Char('N')];
+    CodeWindowReverseStep => "codewindow.reverse_step", "Step backwards into function calls", [Key::Char('S')];
+    CodeWindowReverseFinish => "codewindow.reverse_finish", "Run backwards to the call of the current function", [Key::Char('F')];
+    CodeWindowReverseContinue => "codewindow.reverse_continue", "Run backwards until a breakpoint is hit", [Key::Char('C')];
+    CodeWindowRunToCursor => "codewindow.run_to_cursor", "Run until the line or instruction under the cursor is reached (or the current function returns)", [Key::Char('u')];
+    CodeWindowJumpToCursor => "codewindow.jump_to_cursor", "Move the program counter to the line or instruction under the cursor without executing code", [Key::Char('J')];
+    SrcViewScrollDown => "srcview.scroll_down", "Scroll down", [Key::Down, Key::Char('j')];
+    SrcViewScrollUp => "srcview.scroll_up", "Scroll up", [Key::Up, Key::Char('k')];
+    SrcViewToBeginning => "srcview.to_beginning", "Jump to the first line", [Key::Home];
+    SrcViewToEnd => "srcview.to_end", "Jump to the last line", [Key::End];
+    SrcViewToggleBreakpoint => "srcview.toggle_breakpoint", "Toggle breakpoint at the current line", [Key::Char(' ')];
+    ConsoleScrollUp => "console.scroll_up", "Scroll the console output up", [Key::PageUp];
+    ConsoleScrollDown => "console.scroll_down", "Scroll the console output down", [Key::PageDown];
+    ConsoleToBeginning => "console.to_beginning", "Jump to the beginning of the console output", [Key::Ctrl('b')];
+    ConsoleToEnd => "console.to_end", "Jump to the end of the console output", [Key::Ctrl('e')];
+    ConsoleInterrupt => "console.interrupt", "Interrupt the debugged program", [Key::Ctrl('c')];
+    ExpressionTableNextRow => "expressiontable.next_row", "Advance to the next row", [Key::Char('\n')];
+    ExpressionTableUp => "expressiontable.up", "Select the cell above", [Key::Up];
+    ExpressionTableDown => "expressiontable.down", "Select the cell below", [Key::Down];
+    ExpressionTableLeft => "expressiontable.left", "Select the cell to the left", [Key::Left];
+    ExpressionTableRight => "expressiontable.right", "Select the cell to the right", [Key::Right];
+    ExpressionTableWatch => "expressiontable.watch", "Set a watchpoint on writes to the expression of the selected row", [Key::Ctrl('w')];
+    ExpressionTableReadWatch => "expressiontable.read_watch", "Set a watchpoint on reads of the expression of the selected row", [Key::Ctrl('r')];
+    ExpressionTableAccessWatch => "expressiontable.access_watch", "Set a watchpoint on reads and writes of the expression of the selected row", [Key::Ctrl('x')];
+    BreakpointsToggleEnabled => "breakpoints.toggle_enabled", "Enable or disable the selected breakpoint", [Key::Char(' ')];
+    BreakpointsDelete => "breakpoints.delete", "Delete the selected breakpoint", [Key::Char('d'), Key::Delete];
+    BreakpointsShowLocation => "breakpoints.show_location", "Show the location of the selected breakpoint in the source view", [Key::Char('\n')];
+    BreakpointsEditCondition => "breakpoints.edit_condition", "Edit the condition of the selected breakpoint", [Key::Char('c')];
+    BreakpointsEditIgnoreCount => "breakpoints.edit_ignore_count", "Edit the ignore count of the selected breakpoint", [Key::Char('i')];
+    BreakpointsEditCommands => "breakpoints.edit_commands", "Edit the commands of the selected breakpoint", [Key::Char('m')];
+    BacktraceSelectFrame => "backtrace.select_frame", "Select the highlighted stack frame", [Key::Char('\n')];
+    ThreadsSelectThread => "threads.select_thread", "Switch to the highlighted thread", [Key::Char('\n')];
+    ThreadsInterrupt => "threads.interrupt", "Stop the highlighted thread (all threads in all-stop mode)", [Key::Char('i')];
+    ThreadsContinue => "threads.continue", "Resume the highlighted thread (all threads in all-stop mode)", [Key::Char('c')];
+    LocalsUp => "locals.up", "Select the cell above", [Key::Up];
+    LocalsDown => "locals.down", "Select the cell below", [Key::Down];
+    LocalsLeft => "locals.left", "Select the cell to the left", [Key::Left];
+    LocalsRight => "locals.right", "Select the cell to the right", [Key::Right];
+    MemorySetAddress => "memory.set_address", "Enter the address expression to show", [Key::Char('a')];
+    MemoryWrite => "memory.write", "Write bytes at the selected address", [Key::Char('w')];
+    MemoryLeft => "memory.left", "Select the previous byte", [Key::Left, Key::Char('h')];
+    MemoryRight => "memory.right", "Select the next byte", [Key::Right, Key::Char('l')];
+    MemoryUp => "memory.up", "Select the byte one line above", [Key::Up, Key::Char('k')];
+    MemoryDown => "memory.down", "Select the byte one line below", [Key::Down, Key::Char('j')];
+    MemoryPageUp => "memory.page_up", "Scroll one page up", [Key::PageUp];
+    MemoryPageDown => "memory.page_down", "Scroll one page down", [Key::PageDown];
+    LibrariesLoadSymbols => "libraries.load_symbols", "Load the symbols of the highlighted library", [Key::Char('\n')];
+    RegistersToggleFormat => "registers.toggle_format", "Toggle between hexadecimal and natural register values", [Key::Char('x')];
+    CoreSummarySelectFrame => "coresummary.select_frame", "Switch to the thread and stack frame of the highlighted row", [Key::Char('\n')];
+}
+
+impl Action {
+    pub fn from_name(name: &str) -> Option<Action> {
+        Action::ALL.iter().cloned().find(|a| a.name() == name)
+    }
+}
+
+pub fn parse_key(s: &str) -> Result<Key, String> {
+    let key = match s {
+        "Space" => Key::Char(' '),
+        "Enter" => Key::Char('\n'),
+        "Tab" => Key::Char('\t'),
+        "Esc" => Key::Esc,
+        "Backspace" => Key::Backspace,
+        "Delete" => Key::Delete,
+        "Insert" => Key::Insert,
+        "Up" => Key::Up,
+        "Down" => Key::Down,
+        "Left" => Key::Left,
+        "Right" => Key::Right,
+        "Home" => Key::Home,
+        "End" => Key::End,
+        "PageUp" => Key::PageUp,
+        "PageDown" => Key::PageDown,
+        other => {
+            let chars = other.chars().collect::<Vec<char>>();
+            if chars.len() == 1 {
+                Key::Char(chars[0])
+            } else if chars.len() == 3 && chars[0] == 'C' && chars[1] == '-' {
+                Key::Ctrl(chars[2])
+            } else if chars.len() == 3 && chars[0] == 'M' && chars[1] == '-' {
+                Key::Alt(chars[2])
+            } else if chars.first() == Some(&'F') {
+                match other[1..].parse::<u8>() {
+                    Ok(n) if n >= 1 && n <= 12 => Key::F(n),
+                    _ => return Err(format!("Invalid function key '{}'", other)),
+                }
+            } else if other.split_whitespace().count() > 1 {
+                return Err(format!(
+                    "Key sequences such as '{}' are not supported, bind a single key instead",
+                    other
+                ));
+            } else {
+                return Err(format!("Unknown key '{}'", other));
+            }
+        }
+    };
+    Ok(key)
+}
+
+pub fn format_key(key: &Key) -> String {
+    match key {
+        Key::Char(' ') => "Space".to_owned(),
+        Key::Char('\n') => "Enter".to_owned(),
+        Key::Char('\t') => "Tab".to_owned(),
+        Key::Char(c) => c.to_string(),
+        Key::Ctrl(c) => format!("C-{}", c),
+        Key::Alt(c) => format!("M-{}", c),
+        Key::F(n) => format!("F{}", n),
+        other => format!("{:?}", other),
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Keymap {
+    bindings: HashMap<Action, Vec<Key>>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Keymap {
+            bindings: Action::ALL
+                .iter()
+                .map(|&a| (a, a.default_keys()))
+                .collect(),
+        }
+    }
+}
+
+impl Keymap {
+    pub fn bind(&mut self, action: Action, keys: Vec<Key>) {
+        self.bindings.insert(action, keys);
+    }
+
+    pub fn keys(&self, action: Action) -> &[Key] {
+        self.bindings
+            .get(&action)
+            .map(|keys| keys.as_slice())
+            .unwrap_or(&[])
+    }
+
+    pub fn matches(&self, action: Action, input: &Input) -> bool {
+        self.keys(action).iter().any(|k| input.matches(k.clone()))
+    }
+
+    // Behavior that executes f (and consumes the input) if the input is bound to the action.
+    pub fn on<'k, F: FnOnce() + 'k>(
+        &'k self,
+        action: Action,
+        f: F,
+    ) -> impl FnOnce(Input) -> Option<Input> + 'k {
+        move |input: Input| {
+            if self.matches(action, &input) {
+                f();
+                None
+            } else {
+                Some(input)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_key() {
+        assert_eq!(parse_key("a"), Ok(Key::Char('a')));
+        assert_eq!(parse_key("-"), Ok(Key::Char('-')));
+        assert_eq!(parse_key("Space"), Ok(Key::Char(' ')));
+        assert_eq!(parse_key("C-a"), Ok(Key::Ctrl('a')));
+        assert_eq!(parse_key("M-x"), Ok(Key::Alt('x')));
+        assert_eq!(parse_key("F5"), Ok(Key::F(5)));
+        assert_eq!(parse_key("PageUp"), Ok(Key::PageUp));
+        assert_eq!(parse_key("F"), Ok(Key::Char('F')));
+        assert!(parse_key("F13").is_err());
+        assert!(parse_key("Spacebar").is_err());
+        assert!(parse_key("").is_err());
+        assert!(parse_key("g g").unwrap_err().contains("not supported"));
+    }
+
+    #[test]
+    fn test_format_key_roundtrip() {
+        for action in Action::ALL {
+            for key in Keymap::default().keys(*action) {
+                assert_eq!(parse_key(&format_key(key)).as_ref(), Ok(key));
+            }
+        }
+    }
+
+    #[test]
+    fn test_action_names_unique() {
+        for action in Action::ALL {
+            assert_eq!(Action::from_name(action.name()), Some(*action));
+        }
+    }
+}
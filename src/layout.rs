@@ -0,0 +1,488 @@
+// Parsing of layout expressions that describe how the containers of the tui are arranged.
+//
+// Grammar (whitespace is ignored):
+//   layout    := split
+//   split     := weighted (('|' weighted)* | ('-' weighted)*)
+//   weighted  := weight? node
+//   node      := '(' split ')' | container
+//   weight    := [0-9]+ ('.' [0-9]+)?
+//   container := 's' | 'c' | 'e' | 't' | 'b' | 'f' | 'r' | 'v' | 'g' | 'm' | 'l' | 'p'
+//
+// '|' places its operands side by side, '-' stacks them on top of each other. The optional weight
+// determines the relative share of space an element receives (default: 1).
+use std::collections::HashSet;
+use std::fmt;
+use std::iter::Peekable;
+use std::str::CharIndices;
+use tui::{Tui, TuiContainerType};
+use unsegen::base::{ColIndex, RowIndex};
+use unsegen::container::{
+    ContainerProvider, HorizontalLine, Layout, LayoutOutput, Leaf, Rectangle, VerticalLine,
+};
+use unsegen::widget::{ColDemand, Demand, Demand2D, RowDemand};
+
+pub const DEFAULT_LAYOUT: &str = "(s-c)|(e-t)";
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum LayoutNode {
+    Container(TuiContainerType),
+    HSplit(Vec<(LayoutNode, f64)>),
+    VSplit(Vec<(LayoutNode, f64)>),
+}
+
+impl LayoutNode {
+    pub fn to_layout<'t>(&self) -> Box<Layout<Tui<'t>> + 't> {
+        match self {
+            LayoutNode::Container(c) => Box::new(Leaf::new(c.clone())),
+            LayoutNode::HSplit(elms) => Box::new(WeightedSplit {
+                direction: SplitDirection::Horizontal,
+                elms: elms.iter().map(|(n, w)| (n.to_layout(), *w)).collect(),
+            }),
+            LayoutNode::VSplit(elms) => Box::new(WeightedSplit {
+                direction: SplitDirection::Vertical,
+                elms: elms.iter().map(|(n, w)| (n.to_layout(), *w)).collect(),
+            }),
+        }
+    }
+
+    // All containers that are shown in the layout
+    pub fn containers(&self) -> HashSet<TuiContainerType> {
+        let mut found = HashSet::new();
+        let _ = self.collect_containers(&mut found);
+        found.into_iter().cloned().collect()
+    }
+
+    fn collect_containers<'a>(
+        &'a self,
+        found: &mut HashSet<&'a TuiContainerType>,
+    ) -> Result<(), LayoutParseError> {
+        match self {
+            LayoutNode::Container(c) => {
+                if !found.insert(c) {
+                    return Err(LayoutParseError::DuplicateContainer(c.clone()));
+                }
+            }
+            LayoutNode::HSplit(elms) | LayoutNode::VSplit(elms) => {
+                for (n, _) in elms {
+                    n.collect_containers(found)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum LayoutParseError {
+    UnexpectedEnd,
+    UnexpectedCharacter(usize, char),
+    UnknownContainer(usize, char),
+    MixedSplitDirections(usize),
+    InvalidWeight(usize, String),
+    DuplicateContainer(TuiContainerType),
+}
+
+impl fmt::Display for LayoutParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LayoutParseError::UnexpectedEnd => write!(f, "Unexpected end of layout expression"),
+            LayoutParseError::UnexpectedCharacter(pos, c) => {
+                write!(f, "Unexpected character '{}' at position {}", c, pos)
+            }
+            LayoutParseError::UnknownContainer(pos, c) => write!(
+                f,
+                "Unknown container '{}' at position {} (available: {})",
+                c,
+                pos,
+                CONTAINER_CHARS
+                    .iter()
+                    .map(|(c, _)| c.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            LayoutParseError::MixedSplitDirections(pos) => write!(
+                f,
+                "Mixed '|' and '-' at position {} (use parentheses to nest splits)",
+                pos
+            ),
+            LayoutParseError::InvalidWeight(pos, w) => {
+                write!(f, "Invalid weight '{}' at position {}", w, pos)
+            }
+            LayoutParseError::DuplicateContainer(c) => {
+                write!(f, "Container {:?} appears more than once", c)
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum SplitDirection {
+    Horizontal,
+    Vertical,
+}
+
+// unsegen's HSplit and VSplit share the space equally among their elements, so weighted splits are
+// laid out here. Elements are separated by lines like in unsegen's splits.
+struct WeightedSplit<'a, C: ContainerProvider> {
+    direction: SplitDirection,
+    elms: Vec<(Box<Layout<C> + 'a>, f64)>,
+}
+
+impl<'a, C: ContainerProvider> Layout<C> for WeightedSplit<'a, C> {
+    fn space_demand(&self, containers: &C) -> Demand2D {
+        let separators = self.elms.len().checked_sub(1).unwrap_or(0);
+        let mut width = ColDemand::exact(0);
+        let mut height = RowDemand::exact(0);
+        for (elm, _) in self.elms.iter() {
+            let demand = elm.space_demand(containers);
+            match self.direction {
+                SplitDirection::Horizontal => {
+                    width = width + demand.width;
+                    height = height.max(demand.height);
+                }
+                SplitDirection::Vertical => {
+                    width = width.max(demand.width);
+                    height = height + demand.height;
+                }
+            }
+        }
+        match self.direction {
+            SplitDirection::Horizontal => width = width + ColDemand::exact(separators),
+            SplitDirection::Vertical => height = height + RowDemand::exact(separators),
+        }
+        Demand2D {
+            width: width,
+            height: height,
+        }
+    }
+
+    fn layout(&self, available_area: Rectangle, containers: &C) -> LayoutOutput<C::Index> {
+        let demands = self
+            .elms
+            .iter()
+            .map(|(elm, _)| {
+                let demand = elm.space_demand(containers);
+                match self.direction {
+                    SplitDirection::Horizontal => limits(demand.width),
+                    SplitDirection::Vertical => limits(demand.height),
+                }
+            })
+            .collect::<Vec<_>>();
+        let weights = self.elms.iter().map(|&(_, w)| w).collect::<Vec<_>>();
+        let (begin, end) = match self.direction {
+            SplitDirection::Horizontal => (
+                available_area.x_range.start.raw_value(),
+                available_area.x_range.end.raw_value(),
+            ),
+            SplitDirection::Vertical => (
+                available_area.y_range.start.raw_value(),
+                available_area.y_range.end.raw_value(),
+            ),
+        };
+        let separators = self.elms.len().checked_sub(1).unwrap_or(0);
+        let available = ((end - begin) as usize).checked_sub(separators).unwrap_or(0);
+        let sizes = distribute(available, &demands, &weights);
+
+        let mut output = LayoutOutput {
+            windows: Vec::new(),
+            separators: Vec::new(),
+        };
+        let mut p = begin;
+        for ((elm, _), size) in self.elms.iter().zip(sizes) {
+            let next = p + size as i32;
+            let rect = match self.direction {
+                SplitDirection::Horizontal => Rectangle {
+                    x_range: ColIndex::new(p)..ColIndex::new(next),
+                    y_range: available_area.y_range.clone(),
+                },
+                SplitDirection::Vertical => Rectangle {
+                    x_range: available_area.x_range.clone(),
+                    y_range: RowIndex::new(p)..RowIndex::new(next),
+                },
+            };
+            let child = elm.layout(rect, containers);
+            output.windows.extend(child.windows);
+            output.separators.extend(child.separators);
+            p = next;
+
+            if p < end {
+                output.separators.push(match self.direction {
+                    SplitDirection::Horizontal => HorizontalLine {
+                        x: ColIndex::new(p),
+                        y_range: available_area.y_range.clone(),
+                    }
+                    .into(),
+                    SplitDirection::Vertical => VerticalLine {
+                        x_range: available_area.x_range.clone(),
+                        y: RowIndex::new(p),
+                    }
+                    .into(),
+                });
+                p += 1;
+            }
+        }
+        output
+    }
+}
+
+fn limits<T: ::unsegen::base::AxisDimension>(demand: Demand<T>) -> (usize, Option<usize>) {
+    (demand.min.into(), demand.max.map(|max| max.into()))
+}
+
+// Share the available space proportionally to the weights. Minimum demands (as far as space allows)
+// take precedence over the weights, maximum demands are only exceeded if all elements reached them.
+fn distribute(available: usize, demands: &[(usize, Option<usize>)], weights: &[f64]) -> Vec<usize> {
+    let mut remaining = available;
+    let mut sizes = demands
+        .iter()
+        .map(|&(min, _)| {
+            let size = min.min(remaining);
+            remaining -= size;
+            size
+        })
+        .collect::<Vec<_>>();
+    let mut unbounded = false;
+    while remaining > 0 && !sizes.is_empty() {
+        // Grow the element whose size is furthest below its share.
+        let next = (0..sizes.len())
+            .filter(|&i| unbounded || demands[i].1.map(|max| sizes[i] < max).unwrap_or(true))
+            .min_by(|&i, &j| {
+                let ratio_i = sizes[i] as f64 / weights[i];
+                let ratio_j = sizes[j] as f64 / weights[j];
+                ratio_i.partial_cmp(&ratio_j).expect("weights are positive")
+            });
+        match next {
+            Some(i) => {
+                sizes[i] += 1;
+                remaining -= 1;
+            }
+            None => unbounded = true,
+        }
+    }
+    sizes
+}
+
+const CONTAINER_CHARS: &[(char, TuiContainerType)] = &[
+    ('s', TuiContainerType::SrcView),
+    ('c', TuiContainerType::Console),
+    ('e', TuiContainerType::ExpressionTable),
+    ('t', TuiContainerType::Terminal),
+    ('b', TuiContainerType::Breakpoints),
+    ('f', TuiContainerType::Backtrace),
+    ('r', TuiContainerType::Threads),
+    ('v', TuiContainerType::Locals),
+    ('g', TuiContainerType::Registers),
+    ('m', TuiContainerType::Memory),
+    ('l', TuiContainerType::Libraries),
+    ('p', TuiContainerType::CoreSummary),
+];
+
+struct Parser<'s> {
+    chars: Peekable<CharIndices<'s>>,
+}
+
+impl<'s> Parser<'s> {
+    fn peek(&mut self) -> Option<(usize, char)> {
+        while let Some(&(_, c)) = self.chars.peek() {
+            if c.is_whitespace() {
+                self.chars.next();
+            } else {
+                break;
+            }
+        }
+        self.chars.peek().cloned()
+    }
+
+    fn next(&mut self) -> Option<(usize, char)> {
+        let res = self.peek();
+        self.chars.next();
+        res
+    }
+
+    fn parse_split(&mut self, closing: Option<char>) -> Result<LayoutNode, LayoutParseError> {
+        let mut elements = vec![self.parse_weighted()?];
+        let mut direction = None;
+        loop {
+            match self.peek() {
+                None => {
+                    if closing.is_none() {
+                        break;
+                    } else {
+                        return Err(LayoutParseError::UnexpectedEnd);
+                    }
+                }
+                Some((_, c)) if Some(c) == closing => {
+                    self.next();
+                    break;
+                }
+                Some((i, c)) if c == '|' || c == '-' => {
+                    self.next();
+                    match direction {
+                        None => direction = Some(c),
+                        Some(d) if d != c => return Err(LayoutParseError::MixedSplitDirections(i)),
+                        Some(_) => {}
+                    }
+                    elements.push(self.parse_weighted()?);
+                }
+                Some((i, c)) => return Err(LayoutParseError::UnexpectedCharacter(i, c)),
+            }
+        }
+        Ok(match direction {
+            None => elements.pop().expect("at least one element").0,
+            Some('|') => LayoutNode::HSplit(elements),
+            Some(_) => LayoutNode::VSplit(elements),
+        })
+    }
+
+    fn parse_weight(&mut self) -> Result<f64, LayoutParseError> {
+        let begin = if let Some((i, _)) = self.peek() {
+            i
+        } else {
+            return Err(LayoutParseError::UnexpectedEnd);
+        };
+        let mut weight_str = String::new();
+        while let Some(&(_, c)) = self.chars.peek() {
+            if c.is_digit(10) || c == '.' {
+                weight_str.push(c);
+                self.chars.next();
+            } else {
+                break;
+            }
+        }
+        if weight_str.is_empty() {
+            return Ok(1.0);
+        }
+        match weight_str.parse::<f64>() {
+            Ok(w) if w > 0.0 && w.is_finite() => Ok(w),
+            _ => Err(LayoutParseError::InvalidWeight(begin, weight_str)),
+        }
+    }
+
+    fn parse_weighted(&mut self) -> Result<(LayoutNode, f64), LayoutParseError> {
+        let weight = self.parse_weight()?;
+        Ok((self.parse_node()?, weight))
+    }
+
+    fn parse_node(&mut self) -> Result<LayoutNode, LayoutParseError> {
+        Ok(match self.next() {
+            Some((_, '(')) => self.parse_split(Some(')'))?,
+            Some((i, c)) => CONTAINER_CHARS
+                .iter()
+                .find(|(ch, _)| *ch == c)
+                .map(|(_, t)| LayoutNode::Container(t.clone()))
+                .ok_or_else(|| {
+                    if c.is_alphabetic() {
+                        LayoutParseError::UnknownContainer(i, c)
+                    } else {
+                        LayoutParseError::UnexpectedCharacter(i, c)
+                    }
+                })?,
+            None => return Err(LayoutParseError::UnexpectedEnd),
+        })
+    }
+}
+
+pub fn parse(input: &str) -> Result<LayoutNode, LayoutParseError> {
+    let mut parser = Parser {
+        chars: input.char_indices().peekable(),
+    };
+    let layout = parser.parse_split(None)?;
+    layout.collect_containers(&mut HashSet::new())?;
+    Ok(layout)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn container(c: TuiContainerType) -> (LayoutNode, f64) {
+        (LayoutNode::Container(c), 1.0)
+    }
+
+    #[test]
+    fn test_parse_default() {
+        assert_eq!(
+            parse(DEFAULT_LAYOUT).unwrap(),
+            LayoutNode::HSplit(vec![
+                (
+                    LayoutNode::VSplit(vec![
+                        container(TuiContainerType::SrcView),
+                        container(TuiContainerType::Console),
+                    ]),
+                    1.0
+                ),
+                (
+                    LayoutNode::VSplit(vec![
+                        container(TuiContainerType::ExpressionTable),
+                        container(TuiContainerType::Terminal),
+                    ]),
+                    1.0
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parse_weights_and_whitespace() {
+        assert_eq!(
+            parse(" 3s | 1.5 ( c - e ) ").unwrap(),
+            LayoutNode::HSplit(vec![
+                (LayoutNode::Container(TuiContainerType::SrcView), 3.0),
+                (
+                    LayoutNode::VSplit(vec![
+                        container(TuiContainerType::Console),
+                        container(TuiContainerType::ExpressionTable),
+                    ]),
+                    1.5
+                ),
+            ])
+        );
+        assert_eq!(
+            parse("c").unwrap(),
+            LayoutNode::Container(TuiContainerType::Console)
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(parse(""), Err(LayoutParseError::UnexpectedEnd));
+        assert_eq!(parse("(s|c"), Err(LayoutParseError::UnexpectedEnd));
+        assert_eq!(parse("s|c)"), Err(LayoutParseError::UnexpectedCharacter(3, ')')));
+        assert_eq!(parse("s|x"), Err(LayoutParseError::UnknownContainer(2, 'x')));
+        assert_eq!(parse("s|c-e"), Err(LayoutParseError::MixedSplitDirections(3)));
+        assert_eq!(parse("2"), Err(LayoutParseError::UnexpectedEnd));
+        assert_eq!(
+            parse("0s|c"),
+            Err(LayoutParseError::InvalidWeight(0, "0".to_owned()))
+        );
+        assert_eq!(
+            parse("s|1.2.3c"),
+            Err(LayoutParseError::InvalidWeight(2, "1.2.3".to_owned()))
+        );
+        assert_eq!(
+            parse("s|(c-s)"),
+            Err(LayoutParseError::DuplicateContainer(
+                TuiContainerType::SrcView
+            ))
+        );
+    }
+
+    #[test]
+    fn test_distribute() {
+        let free = (0, None);
+        assert_eq!(distribute(30, &[free, free, free], &[1.0, 1.0, 1.0]), vec![10, 10, 10]);
+        assert_eq!(distribute(31, &[free, free], &[2.0, 1.0]), vec![21, 10]);
+        assert_eq!(distribute(10, &[free, free], &[1.5, 1.0]), vec![6, 4]);
+
+        // Minimum demands take precedence over weights
+        assert_eq!(distribute(30, &[free, (25, None)], &[2.0, 1.0]), vec![5, 25]);
+        assert_eq!(distribute(10, &[(8, None), (8, None)], &[1.0, 1.0]), vec![8, 2]);
+
+        // Maximum demands are only exceeded if no other element can grow
+        assert_eq!(distribute(30, &[(0, Some(5)), free], &[1.0, 1.0]), vec![5, 25]);
+        assert_eq!(distribute(10, &[(0, Some(2)), (0, Some(2))], &[1.0, 1.0]), vec![5, 5]);
+
+        assert_eq!(distribute(0, &[free, free], &[1.0, 1.0]), vec![0, 0]);
+        assert!(distribute(10, &[], &[]).is_empty());
+    }
+}
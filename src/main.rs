@@ -8,6 +8,7 @@ extern crate nix;
 extern crate structopt;
 extern crate termion;
 extern crate time;
+extern crate toml;
 #[macro_use]
 extern crate derive_more;
 
@@ -30,10 +31,13 @@ extern crate nom;
 #[macro_use]
 extern crate lalrpop_util;
 
+mod config;
 mod gdb;
 mod gdb_expression_parsing;
 mod gdbmi;
 mod ipc;
+mod keymap;
+mod layout;
 mod tui;
 
 use std::ffi::OsString;
@@ -43,34 +47,36 @@ use std::time::Duration;
 use chan::{Receiver, Sender};
 use chan_signal::Signal;
 
+use config::{ColorConfig, Config};
 use gdb::GDB;
-use gdbmi::output::OutOfBandRecord;
-use gdbmi::{GDBBuilder, OutOfBandRecordSink};
+use gdbmi::output::{OutOfBandRecord, ResultRecord};
+use gdbmi::{GDBBuilder, OutOfBandRecordSink, ResultRecordSink};
+use keymap::Action;
 use log::{debug, warn};
 use nix::sys::termios;
 use std::path::PathBuf;
 use structopt::StructOpt;
+use tui::attach::AttachPicker;
+use tui::help::HelpOverlay;
+use tui::status_bar::StatusBar;
 use tui::{Tui, TuiContainerType};
-use unsegen::base::{Color, StyleModifier, Terminal};
-use unsegen::container::{ContainerManager, HSplit, Leaf, VSplit};
-use unsegen::input::{Input, Key, NavigateBehavior, ToEvent};
-use unsegen::widget::{Blink, RenderingHints};
+use unsegen::base::{StyleModifier, Terminal};
+use unsegen::container::ContainerManager;
+use unsegen::input::{Input, Navigatable};
+use unsegen::widget::{Blink, RenderingHints, Widget};
 
 const EVENT_BUFFER_DURATION_MS: u64 = 10;
-const FOCUS_ESCAPE_MAX_DURATION_MS: u64 = 200;
-const CURSOR_BLINK_PERIOD_MS: u64 = 500;
-const CURSOR_BLINK_TIMES: u8 = 20;
+const STATUS_MESSAGE_DURATION: Duration = Duration::from_secs(5);
 
 #[derive(StructOpt)]
 #[structopt()]
 struct Options {
     #[structopt(
         long = "gdb",
-        help = "Path to alternative gdb binary.",
-        default_value = "gdb",
+        help = "Path to alternative gdb binary. [default: gdb]",
         parse(from_os_str)
     )]
-    gdb_path: PathBuf,
+    gdb_path: Option<PathBuf>,
     #[structopt(long = "nh", help = "Do not execute commands from ~/.gdbinit.")]
     nh: bool,
     #[structopt(
@@ -112,6 +118,8 @@ struct Options {
     core_file: Option<PathBuf>,
     #[structopt(short = "p", long = "pid", help = "Attach to process with given id.")]
     proc_id: Option<u32>,
+    #[structopt(long = "attach", help = "Choose a running process to attach to.")]
+    attach: bool,
     #[structopt(
         short = "x",
         long = "command",
@@ -126,13 +134,50 @@ struct Options {
         parse(from_os_str)
     )]
     source_dir: Option<PathBuf>,
+    #[structopt(
+        long = "non-stop",
+        help = "Debug in non-stop mode: Threads are stopped and resumed individually while the others keep running."
+    )]
+    non_stop: bool,
+    #[structopt(
+        long = "rr-replay",
+        help = "Replay the rr trace in the given directory. Enables reverse execution.",
+        parse(from_os_str)
+    )]
+    rr_trace_dir: Option<PathBuf>,
+    #[structopt(
+        long = "remote",
+        help = "Connect to gdbserver or another remote stub at the given address (e.g., localhost:1234 or /dev/ttyS0)."
+    )]
+    remote: Option<String>,
+    #[structopt(
+        long = "extended-remote",
+        help = "Use the extended remote protocol for --remote, i.e., stay connected after the program exited."
+    )]
+    extended_remote: bool,
     #[structopt(
         long = "log_dir",
-        help = "Directory in which the log file will be stored",
-        parse(from_os_str),
-        default_value = "/tmp"
+        help = "Directory in which the log file will be stored [default: /tmp]",
+        parse(from_os_str)
+    )]
+    log_dir: Option<PathBuf>,
+    #[structopt(
+        long = "layout",
+        help = "Arrangement of the containers, e.g. \"(2s-c)|(e-t)\". '|' splits horizontally, '-' vertically, optional numbers are relative weights. Containers: s(ource), c(onsole), e(xpression table), t(erminal), b(reakpoints), f(rames/backtrace), (th)r(eads), v(ariables), re(g)isters, m(emory), (shared) l(ibraries), p(ost-mortem core dump summary). [default: (s-c)|(e-t)]",
+        parse(try_from_str = "layout::parse")
+    )]
+    layout: Option<layout::LayoutNode>,
+    #[structopt(
+        long = "theme",
+        help = "Syntax highlighting theme of the source view. [default: base16-ocean.dark]"
+    )]
+    theme: Option<String>,
+    #[structopt(
+        long = "config",
+        help = "Read the configuration from the given file instead of $XDG_CONFIG_HOME/ugdb/config.",
+        parse(from_os_str)
     )]
-    log_dir: PathBuf,
+    config_file: Option<PathBuf>,
     #[structopt(
         help = "Path to program to debug (with arguments).",
         parse(from_os_str)
@@ -146,8 +191,24 @@ struct Options {
 }
 
 impl Options {
-    fn create_gdb_builder(self) -> GDBBuilder {
-        let mut gdb_builder = GDBBuilder::new(self.gdb_path);
+    // Command line arguments take precedence over the configuration file.
+    fn merge_into(&self, config: &mut Config) {
+        if let Some(ref gdb_path) = self.gdb_path {
+            config.gdb_path = gdb_path.clone();
+        }
+        if let Some(ref log_dir) = self.log_dir {
+            config.log_dir = log_dir.clone();
+        }
+        if let Some(ref layout) = self.layout {
+            config.layout = layout.clone();
+        }
+        if let Some(ref theme) = self.theme {
+            config.theme = theme.clone();
+        }
+    }
+
+    fn create_gdb_builder(self, gdb_path: PathBuf) -> GDBBuilder {
+        let mut gdb_builder = GDBBuilder::new(gdb_path);
         if self.nh {
             gdb_builder = gdb_builder.nh();
         }
@@ -178,6 +239,12 @@ impl Options {
         if let Some(src_dir) = self.source_dir {
             gdb_builder = gdb_builder.source_dir(src_dir);
         }
+        if self.non_stop {
+            gdb_builder = gdb_builder.non_stop();
+        }
+        if let Some(trace_dir) = self.rr_trace_dir {
+            gdb_builder = gdb_builder.rr_replay(trace_dir);
+        }
         let (program, args) = self
             .program
             .split_first()
@@ -200,6 +267,14 @@ impl OutOfBandRecordSink for MpscOobRecordSink {
     }
 }
 
+struct MpscResultRecordSink(Sender<ResultRecord>);
+
+impl ResultRecordSink for MpscResultRecordSink {
+    fn send(&self, data: ResultRecord) {
+        self.0.send(data);
+    }
+}
+
 struct MpscSlaveInputSink(Sender<Box<[u8]>>);
 
 impl ::unsegen_terminal::SlaveInputSink for MpscSlaveInputSink {
@@ -223,11 +298,27 @@ impl MessageSink {
     }
 }
 
+pub struct TuiEventSink {
+    events: Vec<tui::TuiEvent>,
+}
+
+impl TuiEventSink {
+    pub fn send(&mut self, event: tui::TuiEvent) {
+        self.events.push(event);
+    }
+    pub fn drain_events(&mut self) -> Vec<tui::TuiEvent> {
+        let mut alt_buffer = Vec::new();
+        ::std::mem::swap(&mut self.events, &mut alt_buffer);
+        alt_buffer
+    }
+}
+
 type UpdateParameters<'u> = &'u mut UpdateParametersStruct;
 
 pub struct UpdateParametersStruct {
     pub gdb: GDB,
     pub message_sink: MessageSink,
+    pub tui_events: TuiEventSink,
 }
 
 // A timer that can be used to receive an event at any time,
@@ -286,11 +377,19 @@ enum InputMode {
 }
 
 impl InputMode {
-    fn associated_border_style(self) -> StyleModifier {
+    fn name(self) -> &'static str {
         match self {
-            InputMode::Normal => StyleModifier::new(),
-            InputMode::Focused => StyleModifier::new().fg_color(Color::Red),
-            InputMode::ContainerSelect => StyleModifier::new().fg_color(Color::LightYellow),
+            InputMode::Normal => "NORMAL",
+            InputMode::Focused => "FOCUSED",
+            InputMode::ContainerSelect => "SELECT",
+        }
+    }
+
+    fn associated_border_style(self, colors: &ColorConfig) -> StyleModifier {
+        match self {
+            InputMode::Normal => colors.normal_border_style(),
+            InputMode::Focused => colors.focused_border_style(),
+            InputMode::ContainerSelect => colors.container_select_border_style(),
         }
     }
 }
@@ -310,7 +409,41 @@ fn run() -> i32 {
     );
 
     let options = Options::from_args();
-    let log_dir = options.log_dir.to_owned();
+    if options.rr_trace_dir.is_some()
+        && (!options.program.is_empty() || options.core_file.is_some() || options.proc_id.is_some())
+    {
+        eprintln!("--rr-replay cannot be combined with a program, core file or process id.");
+        return 0xfc;
+    }
+    if options.remote.is_some() && (options.rr_trace_dir.is_some() || options.core_file.is_some()) {
+        eprintln!("--remote cannot be combined with --rr-replay or a core file.");
+        return 0xfc;
+    }
+    if options.extended_remote && options.remote.is_none() {
+        eprintln!("--extended-remote requires --remote.");
+        return 0xfc;
+    }
+    if options.attach
+        && (options.proc_id.is_some()
+            || options.core_file.is_some()
+            || options.rr_trace_dir.is_some())
+    {
+        eprintln!("--attach cannot be combined with --pid, a core file or --rr-replay.");
+        return 0xfc;
+    }
+    let attach = options.attach;
+    let core_dump = options.core_file.is_some();
+    let remote = options.remote.clone();
+    let extended_remote = options.extended_remote;
+    let mut config = match Config::load(options.config_file.as_ref().map(|p| p.as_path())) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}", e);
+            return 0xfc;
+        }
+    };
+    options.merge_into(&mut config);
+    let log_dir = config.log_dir.to_owned();
 
     ::std::panic::set_hook(Box::new(move |info| {
         // Switch back to main screen
@@ -332,13 +465,30 @@ fn run() -> i32 {
 
     if let Err(e) = flexi_logger::Logger::with_env_or_str("info")
         .log_to_file()
-        .directory(options.log_dir.to_owned())
+        .directory(config.log_dir.to_owned())
         .start()
     {
         eprintln!("Unable to initialize Logger: {}", e);
         return 0xfe;
     }
 
+    let theme_set = unsegen_pager::ThemeSet::load_defaults();
+    let theme = if let Some(theme) = theme_set.themes.get(&config.theme) {
+        theme
+    } else {
+        eprintln!(
+            "Unknown theme '{}'. Available themes: {}",
+            config.theme,
+            theme_set
+                .themes
+                .keys()
+                .cloned()
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        return 0xfc;
+    };
+
     // Create terminal and setup slave input piping
     let (pts_sink, pts_source) = chan::async();
     let tui_terminal =
@@ -348,13 +498,19 @@ fn run() -> i32 {
     let mut ipc = ipc::IPC::setup().expect("Setup ipc");
 
     // Start gdb and setup output event piping
-    let (oob_sink, oob_source) = chan::async();
+    let (oob_sink, mut oob_source) = chan::async();
+    let (async_result_sink, mut async_result_source) = chan::async();
 
-    let mut gdb_builder = options.create_gdb_builder();
+    let mut gdb_builder = options.create_gdb_builder(config.gdb_path.clone());
     gdb_builder = gdb_builder.tty(tui_terminal.slave_name().into());
+    // Kept for respawning gdb with the same arguments after it exited unexpectedly.
+    let respawn_builder = gdb_builder.clone();
     let gdb = GDB::new(
         gdb_builder
-            .try_spawn(MpscOobRecordSink(oob_sink))
+            .try_spawn(
+                MpscOobRecordSink(oob_sink),
+                MpscResultRecordSink(async_result_sink),
+            )
             .expect("spawn gdb"),
     );
 
@@ -363,25 +519,26 @@ fn run() -> i32 {
 
     let stdout = std::io::stdout();
 
-    let theme_set = unsegen_pager::ThemeSet::load_defaults();
-
-    let left_pane = VSplit::new(vec![
-        Box::new(Leaf::new(TuiContainerType::SrcView)),
-        Box::new(Leaf::new(TuiContainerType::Console)),
-    ]);
-    let right_pane = VSplit::new(vec![
-        Box::new(Leaf::new(TuiContainerType::ExpressionTable)),
-        Box::new(Leaf::new(TuiContainerType::Terminal)),
-    ]);
-    let layout = HSplit::new(vec![Box::new(left_pane), Box::new(right_pane)]);
-
     let mut update_parameters = UpdateParametersStruct {
         gdb: gdb,
         message_sink: MessageSink {
             messages: Vec::new(),
         },
+        tui_events: TuiEventSink { events: Vec::new() },
     };
 
+    if let Some(ref address) = remote {
+        match update_parameters.gdb.connect_remote(address, extended_remote) {
+            Ok(()) => update_parameters
+                .message_sink
+                .send(format!("Connected to remote target {}.", address)),
+            Err(e) => update_parameters.message_sink.send(format!(
+                "Cannot connect to remote target {}: {}. Use '!reconnect' to try again.",
+                address, e
+            )),
+        }
+    }
+
     {
         let mut terminal = match Terminal::new(stdout.lock()) {
             Ok(t) => t,
@@ -390,7 +547,14 @@ fn run() -> i32 {
                 return 0xfd;
             }
         };
-        let mut tui = Tui::new(tui_terminal, &theme_set.themes["base16-ocean.dark"]);
+        let keymap = &config.keymap;
+        let mut tui = Tui::new(tui_terminal, theme, keymap, config.layout.containers());
+        if attach {
+            tui.attach_picker = Some(AttachPicker::new(keymap));
+        }
+        if core_dump {
+            tui.show_core_dump(&mut update_parameters);
+        }
 
         // Start stdin thread _after_ building terminal (and setting the actual terminal to raw
         // mode to avoid race condition where the first 'set of input' is buffered
@@ -403,25 +567,43 @@ fn run() -> i32 {
             }
         });
 
-        let mut app = ContainerManager::<Tui>::from_layout(Box::new(layout));
+        let mut app = ContainerManager::<Tui>::from_layout(config.layout.to_layout());
         let mut input_mode = InputMode::Normal;
         let mut focus_esc_timer = MpscTimer::new();
+        // The first press of the escape key in focused mode is withheld until it is clear that it
+        // was not meant to leave the container.
+        let mut pending_focus_escape: Option<Input> = None;
+        let mut help: Option<HelpOverlay> = None;
         let mut cursor_status = Blink::On;
         let mut cursor_blinks_since_last_input = 0;
 
+        // Last message of the MessageSink, shown in the status bar for a while
+        let mut status_message: Option<(String, ::std::time::Instant)> = None;
+
+        // Sinks of the channels for the next gdb process after gdb exited unexpectedly
+        let mut respawn_sinks: Option<(Sender<OutOfBandRecord>, Sender<ResultRecord>)> = None;
+
+        let ipc_socket = ipc.socket_path().to_owned();
         // Somehow ipc.requests does not work in the chan_select macro...
         let ipc_requests = &mut ipc.requests;
 
         'runloop: loop {
             let mut cursor_update_timer = MpscTimer::new();
-            if cursor_blinks_since_last_input < CURSOR_BLINK_TIMES {
-                cursor_update_timer.try_start(Duration::from_millis(CURSOR_BLINK_PERIOD_MS));
+            if cursor_blinks_since_last_input < config.timing.cursor_blink_times {
+                cursor_update_timer
+                    .try_start(Duration::from_millis(config.timing.cursor_blink_period_ms));
+            }
+
+            let mut command_timeout_timer = MpscTimer::new();
+            if let Some(timeout) = update_parameters.gdb.mi.next_timeout() {
+                command_timeout_timer.try_start(timeout);
             }
 
             let mut render_delay_timer = MpscTimer::new();
             let mut esc_timer_needs_reset = false;
             'displayloop: loop {
                 let mut esc_in_focused_context_pressed = false;
+                let mut gdb_exited = false;
                 #[allow(unused_mut)]
                 {
                     // Not sure where the unused mut in the chan_select macro is coming from...
@@ -436,8 +618,14 @@ fn run() -> i32 {
                             cursor_blinks_since_last_input = 0;
                             break 'displayloop;
                         },
+                        command_timeout_timer.recv() => {
+                            tui.handle_command_timeouts(&mut update_parameters);
+                            break 'displayloop;
+                        },
                         focus_esc_timer.recv() => {
-                            Input { event: Key::Esc.to_event(), raw: vec![0x1bu8] }.chain(app.active_container_behavior(&mut tui, &mut update_parameters));
+                            if let Some(input) = pending_focus_escape.take() {
+                                input.chain(app.active_container_behavior(&mut tui, &mut update_parameters));
+                            }
                             esc_timer_needs_reset = true;
                             break 'displayloop;
                         },
@@ -445,44 +633,72 @@ fn run() -> i32 {
                             let sig_behavior = ::unsegen_signals::SignalBehavior::new().on_default::<::unsegen_signals::SIGTSTP>();
                             let input = input.expect("read keyboard event")
                                 .chain(sig_behavior);
-                            match input_mode {
-                                InputMode::ContainerSelect => {
-                                    input
-                                        .chain(NavigateBehavior::new(&mut app.navigatable(&mut tui))
-                                               .up_on(Key::Char('k'))
-                                               .up_on(Key::Up)
-                                               .down_on(Key::Char('j'))
-                                               .down_on(Key::Down)
-                                               .left_on(Key::Char('h'))
-                                               .left_on(Key::Left)
-                                               .right_on(Key::Char('l'))
-                                               .right_on(Key::Right)
-                                              )
-                                        .chain((Key::Char('i'), || { input_mode = InputMode::Normal; app.set_active(TuiContainerType::Console); }))
-                                        .chain((Key::Char('e'), || { input_mode = InputMode::Normal; app.set_active(TuiContainerType::ExpressionTable); }))
-                                        .chain((Key::Char('s'), || { input_mode = InputMode::Normal; app.set_active(TuiContainerType::SrcView); }))
-                                        .chain((Key::Char('t'), || { input_mode = InputMode::Normal; app.set_active(TuiContainerType::Terminal); }))
-                                        .chain((Key::Char('T'), || { input_mode = InputMode::Focused; app.set_active(TuiContainerType::Terminal); }))
-                                        .chain((Key::Char('\n'), || input_mode = InputMode::Normal ))
-                                }
-                                InputMode::Normal => {
-                                    input
-                                        .chain((Key::Esc, || input_mode = InputMode::ContainerSelect ))
-                                        .chain(app.active_container_behavior(&mut tui, &mut update_parameters))
-                                }
-                                InputMode::Focused => {
-                                    input
-                                        .chain((Key::Esc, || esc_in_focused_context_pressed = true ))
-                                        .chain(app.active_container_behavior(&mut tui, &mut update_parameters))
+                            if let Some(mut overlay) = help.take() {
+                                // Any key that does not scroll the help overlay closes it
+                                if input.chain(|i: Input| overlay.input(i)).finish().is_none() {
+                                    help = Some(overlay);
                                 }
-                            }.finish();
+                            } else if let Some(ref mut picker) = tui.attach_picker {
+                                input.chain(|i: Input| picker.input(i, &mut update_parameters)).finish();
+                            } else {
+                                match input_mode {
+                                    InputMode::ContainerSelect => {
+                                        input
+                                            .chain(keymap.on(Action::ContainerUp, || { let _ = app.navigatable(&mut tui).move_up(); }))
+                                            .chain(keymap.on(Action::ContainerDown, || { let _ = app.navigatable(&mut tui).move_down(); }))
+                                            .chain(keymap.on(Action::ContainerLeft, || { let _ = app.navigatable(&mut tui).move_left(); }))
+                                            .chain(keymap.on(Action::ContainerRight, || { let _ = app.navigatable(&mut tui).move_right(); }))
+                                            .chain(keymap.on(Action::SelectConsole, || { input_mode = InputMode::Normal; app.set_active(TuiContainerType::Console); }))
+                                            .chain(keymap.on(Action::SelectExpressionTable, || { input_mode = InputMode::Normal; app.set_active(TuiContainerType::ExpressionTable); }))
+                                            .chain(keymap.on(Action::SelectSrcView, || { input_mode = InputMode::Normal; app.set_active(TuiContainerType::SrcView); }))
+                                            .chain(keymap.on(Action::SelectTerminal, || { input_mode = InputMode::Normal; app.set_active(TuiContainerType::Terminal); }))
+                                            .chain(keymap.on(Action::SelectBreakpoints, || { input_mode = InputMode::Normal; app.set_active(TuiContainerType::Breakpoints); }))
+                                            .chain(keymap.on(Action::SelectBacktrace, || { input_mode = InputMode::Normal; app.set_active(TuiContainerType::Backtrace); }))
+                                            .chain(keymap.on(Action::SelectThreads, || { input_mode = InputMode::Normal; app.set_active(TuiContainerType::Threads); }))
+                                            .chain(keymap.on(Action::SelectLocals, || { input_mode = InputMode::Normal; app.set_active(TuiContainerType::Locals); }))
+                                            .chain(keymap.on(Action::SelectRegisters, || { input_mode = InputMode::Normal; app.set_active(TuiContainerType::Registers); }))
+                                            .chain(keymap.on(Action::SelectMemory, || { input_mode = InputMode::Normal; app.set_active(TuiContainerType::Memory); }))
+                                            .chain(keymap.on(Action::SelectLibraries, || { input_mode = InputMode::Normal; app.set_active(TuiContainerType::Libraries); }))
+                                            .chain(keymap.on(Action::SelectCoreSummary, || { input_mode = InputMode::Normal; app.set_active(TuiContainerType::CoreSummary); }))
+                                            .chain(keymap.on(Action::FocusTerminal, || { input_mode = InputMode::Focused; app.set_active(TuiContainerType::Terminal); }))
+                                            .chain(keymap.on(Action::ShowHelp, || help = Some(HelpOverlay::new(keymap)) ))
+                                            .chain(keymap.on(Action::LeaveContainerSelect, || input_mode = InputMode::Normal ))
+                                    }
+                                    InputMode::Normal => {
+                                        input
+                                            .chain(keymap.on(Action::EnterContainerSelect, || input_mode = InputMode::ContainerSelect ))
+                                            .chain(|i: Input| tui.handle_global_stepping_keys(i, &mut update_parameters))
+                                            .chain(app.active_container_behavior(&mut tui, &mut update_parameters))
+                                    }
+                                    InputMode::Focused => {
+                                        input
+                                            .chain(|i: Input| {
+                                                if keymap.matches(Action::EnterContainerSelect, &i) {
+                                                    esc_in_focused_context_pressed = true;
+                                                    if pending_focus_escape.is_none() {
+                                                        pending_focus_escape = Some(i);
+                                                    }
+                                                    None
+                                                } else {
+                                                    Some(i)
+                                                }
+                                            })
+                                            .chain(app.active_container_behavior(&mut tui, &mut update_parameters))
+                                    }
+                                }.finish();
+                            }
                         },
                         oob_source.recv() -> oob_evt => {
                             if let Some(record) = oob_evt {
                                 tui.add_out_of_band_record(record, &mut update_parameters);
                             } else {
                                 // OOB pipe has closed. => gdb will be stopping soon
-                                break 'runloop;
+                                gdb_exited = true;
+                            }
+                        },
+                        async_result_source.recv() -> record => {
+                            if let Some(record) = record {
+                                tui.add_result_record(record, &mut update_parameters);
                             }
                         },
                         ipc_requests.recv() -> request => {
@@ -505,12 +721,78 @@ fn run() -> i32 {
                         },
                     }
                 }
+                if gdb_exited {
+                    if update_parameters.gdb.mi.has_quit() {
+                        break 'runloop;
+                    }
+                    // gdb crashed or was killed: Keep the ui alive and offer to start it again.
+                    let status = match update_parameters.gdb.mi.process.wait() {
+                        Ok(status) => status.to_string(),
+                        Err(e) => format!("unknown status: {}", e),
+                    };
+                    // Replace the closed channels so that we do not keep receiving their end.
+                    let (oob_sink, new_oob_source) = chan::async();
+                    let (async_result_sink, new_async_result_source) = chan::async();
+                    oob_source = new_oob_source;
+                    async_result_source = new_async_result_source;
+                    respawn_sinks = Some((oob_sink, async_result_sink));
+                    update_parameters.gdb.unexpected_exit = Some(status);
+                    tui.console.ask_for_respawn(&mut update_parameters);
+                }
+                if update_parameters.gdb.respawn_requested {
+                    update_parameters.gdb.respawn_requested = false;
+                    if let Some((oob_sink, async_result_sink)) = respawn_sinks.clone() {
+                        match respawn_builder.clone().try_spawn(
+                            MpscOobRecordSink(oob_sink),
+                            MpscResultRecordSink(async_result_sink),
+                        ) {
+                            Ok(mi) => {
+                                respawn_sinks = None;
+                                let old_gdb = ::std::mem::replace(
+                                    &mut update_parameters.gdb,
+                                    GDB::new(mi),
+                                );
+                                update_parameters.gdb.remote = old_gdb.remote.clone();
+                                if update_parameters.gdb.remote.is_some() {
+                                    if let Err(e) = update_parameters.gdb.reconnect_remote() {
+                                        update_parameters.message_sink.send(format!(
+                                            "Cannot reconnect to the remote target: {}",
+                                            e
+                                        ));
+                                    }
+                                }
+                                for (number, e) in update_parameters
+                                    .gdb
+                                    .restore_breakpoints(&old_gdb.breakpoints)
+                                {
+                                    update_parameters.message_sink.send(format!(
+                                        "Cannot restore breakpoint {}: {}",
+                                        number, e
+                                    ));
+                                }
+                                tui.handle_gdb_respawn(&mut update_parameters);
+                                update_parameters.message_sink.send("Respawned gdb.");
+                                if core_dump {
+                                    tui.show_core_dump(&mut update_parameters);
+                                }
+                            }
+                            Err(e) => {
+                                update_parameters.message_sink.send(format!(
+                                    "Cannot respawn gdb: {}. Use '!respawn' to try again.",
+                                    e
+                                ));
+                            }
+                        }
+                    }
+                }
                 if esc_in_focused_context_pressed {
                     if focus_esc_timer.has_been_started() {
                         input_mode = InputMode::ContainerSelect;
+                        pending_focus_escape = None;
                     } else {
-                        focus_esc_timer
-                            .try_start(Duration::from_millis(FOCUS_ESCAPE_MAX_DURATION_MS));
+                        focus_esc_timer.try_start(Duration::from_millis(
+                            config.timing.focus_escape_max_duration_ms,
+                        ));
                     }
                 }
                 tui.update_after_event(&mut update_parameters);
@@ -519,14 +801,47 @@ fn run() -> i32 {
             if esc_timer_needs_reset {
                 focus_esc_timer.reset();
             }
-            tui.console
-                .display_messages(&mut update_parameters.message_sink);
-            app.draw(
-                terminal.create_root_window(),
-                &mut tui,
-                input_mode.associated_border_style(),
-                RenderingHints::default().blink(cursor_status),
-            );
+            let messages = update_parameters.message_sink.drain_messages();
+            if let Some(message) = messages.last() {
+                status_message = Some((message.clone(), ::std::time::Instant::now()));
+            }
+            tui.console.display_messages(&messages);
+
+            let root_window = terminal.create_root_window();
+            let status_row = (root_window.get_height() - 1).from_origin();
+            let (window, status_window) = match root_window.split(status_row) {
+                Ok((window, status_window)) => (window, Some(status_window)),
+                Err(window) => (window, None),
+            };
+            if let Some(ref overlay) = help {
+                overlay.draw(
+                    window,
+                    RenderingHints::default().blink(cursor_status),
+                );
+            } else if let Some(ref picker) = tui.attach_picker {
+                picker.draw(window, RenderingHints::default().blink(cursor_status));
+            } else {
+                app.draw(
+                    window,
+                    &mut tui,
+                    input_mode.associated_border_style(&config.colors),
+                    RenderingHints::default().blink(cursor_status),
+                );
+            }
+            if let Some(status_window) = status_window {
+                let message = status_message
+                    .as_ref()
+                    .filter(|&&(_, time)| time.elapsed() < STATUS_MESSAGE_DURATION)
+                    .map(|&(ref message, _)| message.as_str());
+                StatusBar::new(
+                    input_mode.name(),
+                    &update_parameters.gdb,
+                    tui.selected_frame(),
+                    &ipc_socket,
+                    message,
+                )
+                .draw(status_window, RenderingHints::default());
+            }
             terminal.present();
         }
     }
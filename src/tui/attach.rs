@@ -0,0 +1,241 @@
+use keymap::{Action, Keymap};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use tui::select_list::{ListRow, SelectList};
+
+use unsegen::base::{GraphemeCluster, Window};
+use unsegen::input::{EditBehavior, Input, Key};
+use unsegen::widget::builtin::PromptLine;
+use unsegen::widget::{Demand2D, RenderingHints, SeparatingStyle, VerticalLayout, Widget};
+
+pub struct Process {
+    pub pid: u32,
+    pub user: String,
+    pub command_line: String,
+}
+
+fn user_names() -> HashMap<u32, String> {
+    fs::read_to_string("/etc/passwd")
+        .map(|passwd| {
+            passwd
+                .lines()
+                .filter_map(|line| {
+                    let mut fields = line.split(':');
+                    let name = fields.next()?;
+                    let uid = fields.nth(1)?.parse::<u32>().ok()?;
+                    Some((uid, name.to_owned()))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn read_process(pid: u32, users: &HashMap<u32, String>) -> Option<Process> {
+    let dir = Path::new("/proc").join(pid.to_string());
+    let command_line = fs::read(dir.join("cmdline"))
+        .ok()?
+        .split(|&b| b == 0)
+        .filter(|arg| !arg.is_empty())
+        .map(|arg| String::from_utf8_lossy(arg).into_owned())
+        .collect::<Vec<_>>()
+        .join(" ");
+    let command_line = if command_line.is_empty() {
+        // Kernel threads (and zombies) do not have a command line.
+        format!("[{}]", fs::read_to_string(dir.join("comm")).ok()?.trim())
+    } else {
+        command_line
+    };
+    let uid = fs::read_to_string(dir.join("status"))
+        .ok()?
+        .lines()
+        .find(|line| line.starts_with("Uid:"))
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|uid| uid.parse::<u32>().ok());
+    let user = match uid {
+        Some(uid) => users.get(&uid).cloned().unwrap_or_else(|| uid.to_string()),
+        None => String::new(),
+    };
+    Some(Process {
+        pid: pid,
+        user: user,
+        command_line: command_line,
+    })
+}
+
+// All processes (except ugdb itself) that are visible in /proc.
+pub fn list_processes() -> Vec<Process> {
+    let users = user_names();
+    let own_pid = ::std::process::id();
+    let mut processes = fs::read_dir("/proc")
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter_map(|entry| entry.file_name().to_str()?.parse::<u32>().ok())
+                .filter(|&pid| pid != own_pid)
+                .filter_map(|pid| read_process(pid, &users))
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    // Recently started processes first
+    processes.sort_by(|a, b| b.pid.cmp(&a.pid));
+    processes
+}
+
+// Case insensitive subsequence match of the pattern (ignoring whitespace) in the text. Lower
+// scores are better: The matched characters are close together and near the beginning.
+fn fuzzy_score(pattern: &str, text: &str) -> Option<(usize, usize)> {
+    let text = text.to_lowercase().chars().collect::<Vec<char>>();
+    let mut first = None;
+    let mut last = 0;
+    let mut pos = 0;
+    for p in pattern.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let index = pos + text[pos..].iter().position(|&c| c == p)?;
+        first.get_or_insert(index);
+        last = index;
+        pos = index + 1;
+    }
+    Some(match first {
+        Some(first) => (last - first, first),
+        None => (0, 0),
+    })
+}
+
+static FILTER_PROMPT: &'static str = "Attach to process (Enter: attach, Esc: cancel) > ";
+
+// Dialog for choosing a process to attach to.
+pub struct AttachPicker<'a> {
+    keymap: &'a Keymap,
+    processes: Vec<Process>,
+    filter: PromptLine,
+    list: SelectList,
+    // Pid of each row of the list
+    pids: Vec<u32>,
+    layout: VerticalLayout,
+    finished: bool,
+}
+
+impl<'a> AttachPicker<'a> {
+    pub fn new(keymap: &'a Keymap) -> Self {
+        let mut picker = AttachPicker {
+            keymap: keymap,
+            processes: list_processes(),
+            filter: PromptLine::with_prompt(FILTER_PROMPT.into()),
+            list: SelectList::new(vec!["PID", "User", "Command"]),
+            pids: Vec::new(),
+            layout: VerticalLayout::new(SeparatingStyle::Draw(
+                GraphemeCluster::try_from('=').unwrap(),
+            )),
+            finished: false,
+        };
+        picker.update_rows();
+        picker
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    fn update_rows(&mut self) {
+        let pattern = self.filter.active_line().to_owned();
+        let mut matches = self
+            .processes
+            .iter()
+            .filter_map(|process| {
+                let text = format!("{} {} {}", process.pid, process.user, process.command_line);
+                fuzzy_score(&pattern, &text).map(|score| (score, process))
+            })
+            .collect::<Vec<_>>();
+        // The sort is stable, so processes with the same score remain ordered by pid.
+        matches.sort_by_key(|&(score, _)| score);
+        self.pids = matches.iter().map(|&(_, process)| process.pid).collect();
+        self.list.set_rows(
+            matches
+                .iter()
+                .map(|&(_, process)| {
+                    ListRow::new(vec![
+                        process.pid.to_string(),
+                        process.user.clone(),
+                        process.command_line.clone(),
+                    ])
+                })
+                .collect(),
+        );
+        self.list.select_first();
+    }
+
+    fn attach(&mut self, p: ::UpdateParameters) {
+        if let Some(&pid) = self.list.selected().and_then(|row| self.pids.get(row)) {
+            match p.gdb.attach(pid) {
+                Ok(()) => {
+                    p.message_sink.send(format!("Attached to process {}.", pid));
+                    self.finished = true;
+                }
+                Err(e) => p.message_sink.send(e.to_string()),
+            }
+        }
+    }
+
+    pub fn input(&mut self, input: Input, p: ::UpdateParameters) -> Option<Input> {
+        let keymap = self.keymap;
+        let previous_filter = self.filter.active_line().to_owned();
+        let remaining = input
+            .chain(keymap.on(Action::PromptSubmit, || self.attach(p)))
+            .chain(keymap.on(Action::PromptCancel, || self.finished = true))
+            .chain(keymap.on(Action::EnterContainerSelect, || self.finished = true))
+            .chain(
+                EditBehavior::new(&mut self.filter)
+                    .left_on(Key::Left)
+                    .right_on(Key::Right)
+                    .delete_forwards_on(Key::Delete)
+                    .delete_backwards_on(Key::Backspace)
+                    .go_to_beginning_of_line_on(Key::Home)
+                    .go_to_end_of_line_on(Key::End),
+            )
+            .chain(keymap.on(Action::ListUp, || self.list.select_previous()))
+            .chain(keymap.on(Action::ListDown, || self.list.select_next()))
+            .finish();
+        if self.filter.active_line() != previous_filter {
+            self.update_rows();
+        }
+        remaining
+    }
+}
+
+impl<'a> Widget for AttachPicker<'a> {
+    fn space_demand(&self) -> Demand2D {
+        let widgets: Vec<&Widget> = vec![&self.filter, &self.list];
+        self.layout.space_demand(widgets.as_slice())
+    }
+    fn draw(&self, window: Window, hints: RenderingHints) {
+        self.layout
+            .draw(window, &[(&self.filter, hints), (&self.list, hints)])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_fuzzy_score() {
+        assert_eq!(fuzzy_score("", "1234 root /usr/bin/app"), Some((0, 0)));
+        assert_eq!(fuzzy_score("app", "1234 root /usr/bin/app"), Some((2, 19)));
+        assert_eq!(fuzzy_score("APP", "1234 root /usr/bin/app"), Some((2, 19)));
+        assert!(fuzzy_score("rtapp", "1234 root /usr/bin/app").is_some());
+        assert!(fuzzy_score("appx", "1234 root /usr/bin/app").is_none());
+        assert!(fuzzy_score("pa", "1234 root /usr/bin/app").is_none());
+
+        // Contiguous matches are preferred
+        let contiguous = fuzzy_score("bin", "1 user /usr/bin/x").unwrap();
+        let scattered = fuzzy_score("bin", "1 user ./b-i-n").unwrap();
+        assert!(contiguous.0 < scattered.0);
+    }
+
+    #[test]
+    fn test_list_processes() {
+        let processes = list_processes();
+        assert!(processes.iter().all(|p| p.pid != ::std::process::id()));
+        assert!(processes.windows(2).all(|w| w[0].pid > w[1].pid));
+    }
+}
@@ -0,0 +1,133 @@
+use gdb::response::get_u64_obj;
+use gdb::StackFrame;
+use gdbmi::output::Object;
+use keymap::{Action, Keymap};
+use log::warn;
+use tui::select_list::{ListRow, SelectList};
+use tui::TuiEvent;
+
+use unsegen::base::{StyleModifier, Window};
+use unsegen::container::Container;
+use unsegen::input::Input;
+use unsegen::widget::{Demand2D, RenderingHints, Widget};
+
+// Deeply recursive programs may have a huge number of frames. We only list the innermost ones.
+const MAX_FRAMES: u64 = 1000;
+
+pub struct Backtrace<'a> {
+    keymap: &'a Keymap,
+    list: SelectList,
+    frames: Vec<StackFrame>,
+    current_level: u64,
+}
+
+impl<'a> Backtrace<'a> {
+    pub fn new(keymap: &'a Keymap) -> Self {
+        Backtrace {
+            keymap: keymap,
+            list: SelectList::new(vec!["#", "Function", "Arguments", "Location", "Address"]),
+            frames: Vec::new(),
+            current_level: 0,
+        }
+    }
+
+    fn format_row(&self, frame: &StackFrame) -> ListRow {
+        let arguments = frame
+            .arguments
+            .iter()
+            .map(|&(ref name, ref value)| match value {
+                &Some(ref value) => format!("{}={}", name, value),
+                &None => format!("{}=...", name),
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        let location = match (&frame.src_pos, &frame.library) {
+            (&Some(ref pos), _) => format!("{}:{}", pos.file.to_string_lossy(), pos.line),
+            (&None, &Some(ref library)) => library.clone(),
+            (&None, &None) => String::new(),
+        };
+        let style = if frame.level == self.current_level {
+            StyleModifier::new().bold(true)
+        } else {
+            StyleModifier::new()
+        };
+        ListRow::new(vec![
+            frame.level.to_string(),
+            frame.function.clone().unwrap_or_else(|| "??".to_owned()),
+            arguments,
+            location,
+            frame
+                .address
+                .map(|a| a.to_string())
+                .unwrap_or_default(),
+        ])
+        .style(style)
+    }
+
+    fn update_rows(&mut self) {
+        let rows = self.frames.iter().map(|f| self.format_row(f)).collect();
+        self.list.set_rows(rows);
+    }
+
+    // Reload all frames, e.g., after the program stopped.
+    pub fn update(&mut self, selected_frame: Option<&Object>, p: ::UpdateParameters) {
+        self.frames = match p.gdb.get_backtrace(MAX_FRAMES) {
+            Ok(frames) => frames,
+            Err(e) => {
+                warn!("Failed to get backtrace: {:?}", e);
+                Vec::new()
+            }
+        };
+        self.current_level = selected_frame
+            .and_then(|f| get_u64_obj(f, "level").ok())
+            .unwrap_or(0);
+        self.update_rows();
+        self.list.select(self.current_level as usize);
+    }
+
+    pub fn set_current_frame(&mut self, frame: &Object) {
+        if let Ok(level) = get_u64_obj(frame, "level") {
+            self.current_level = level;
+            self.update_rows();
+            self.list.select(level as usize);
+        }
+    }
+
+    fn select_frame(&mut self, p: ::UpdateParameters) {
+        if let Some(level) = self
+            .list
+            .selected()
+            .and_then(|row| self.frames.get(row))
+            .map(|f| f.level)
+        {
+            match p.gdb.select_frame(level) {
+                Ok(frame) => p.tui_events.send(TuiEvent::FrameSelected(frame)),
+                Err(e) => p
+                    .message_sink
+                    .send(format!("Cannot select frame {}: {:?}", level, e)),
+            }
+        }
+    }
+}
+
+impl<'a> Widget for Backtrace<'a> {
+    fn space_demand(&self) -> Demand2D {
+        self.list.space_demand()
+    }
+    fn draw(&self, window: Window, hints: RenderingHints) {
+        self.list.draw(window, hints)
+    }
+}
+
+impl<'a> Container<::UpdateParametersStruct> for Backtrace<'a> {
+    fn input(&mut self, input: Input, p: ::UpdateParameters) -> Option<Input> {
+        let keymap = self.keymap;
+        input
+            .chain(keymap.on(Action::ListUp, || self.list.select_previous()))
+            .chain(keymap.on(Action::ListDown, || self.list.select_next()))
+            .chain(keymap.on(Action::ListToBeginning, || self.list.select_first()))
+            .chain(keymap.on(Action::ListToEnd, || self.list.select_last()))
+            .chain(keymap.on(Action::BacktraceSelectFrame, || self.select_frame(p)))
+            .finish()
+    }
+}
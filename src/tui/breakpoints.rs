@@ -0,0 +1,341 @@
+use gdb::BreakPoint;
+use gdbmi::commands::BreakPointNumber;
+use keymap::{Action, Keymap};
+use tui::select_list::{ListRow, SelectList};
+use tui::TuiEvent;
+
+use unsegen::base::{Color, GraphemeCluster, StyleModifier, Window};
+use unsegen::container::Container;
+use unsegen::input::{EditBehavior, Input, Key};
+use unsegen::widget::builtin::PromptLine;
+use unsegen::widget::{Demand2D, RenderingHints, SeparatingStyle, VerticalLayout, Widget};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Property {
+    Condition,
+    IgnoreCount,
+    Commands,
+}
+
+impl Property {
+    fn prompt(self, number: BreakPointNumber) -> String {
+        match self {
+            Property::Condition => format!("Condition of {}: ", number),
+            Property::IgnoreCount => format!("Ignore count of {}: ", number),
+            Property::Commands => format!("Commands of {} (separated by ';'): ", number),
+        }
+    }
+
+    // Text that the prompt starts with so that the current value can be modified.
+    fn current_value(self, bp: &BreakPoint) -> String {
+        match self {
+            Property::Condition => bp.condition.clone().unwrap_or_default(),
+            Property::IgnoreCount => bp.ignore_count.to_string(),
+            Property::Commands => bp.commands.join("; "),
+        }
+    }
+}
+
+static IDLE_PROMPT: &'static str = "";
+
+pub struct BreakpointList<'a> {
+    keymap: &'a Keymap,
+    list: SelectList,
+    // Breakpoint number of each row of the list
+    numbers: Vec<BreakPointNumber>,
+    prompt_line: PromptLine,
+    editing: Option<(BreakPointNumber, Property)>,
+    layout: VerticalLayout,
+    last_bp_update: ::std::time::Instant,
+}
+
+impl<'a> BreakpointList<'a> {
+    pub fn new(keymap: &'a Keymap) -> Self {
+        BreakpointList {
+            keymap: keymap,
+            list: SelectList::new(vec![
+                "Num",
+                "Type",
+                "Enb",
+                "Hits",
+                "Ignore",
+                "Condition",
+                "Commands",
+                "Location",
+            ]),
+            numbers: Vec::new(),
+            prompt_line: PromptLine::with_prompt(IDLE_PROMPT.into()),
+            editing: None,
+            layout: VerticalLayout::new(SeparatingStyle::Draw(
+                GraphemeCluster::try_from('=').unwrap(),
+            )),
+            last_bp_update: ::std::time::Instant::now(),
+        }
+    }
+
+    fn format_row(bp: &BreakPoint) -> ListRow {
+        let number = if bp.number.minor.is_some() {
+            format!("  {}", bp.number)
+        } else {
+            bp.number.to_string()
+        };
+        let location = if let Some(ref expression) = bp.expression {
+            // Watchpoints have no location, only the watched expression
+            expression.clone()
+        } else {
+            match (&bp.src_pos, &bp.function, bp.address) {
+                (&Some(ref pos), &Some(ref func), _) => format!(
+                    "{} at {}:{}",
+                    func,
+                    pos.file.to_string_lossy(),
+                    pos.line
+                ),
+                (&Some(ref pos), &None, _) => {
+                    format!("{}:{}", pos.file.to_string_lossy(), pos.line)
+                }
+                (&None, &Some(ref func), Some(address)) => format!("{} at {}", func, address),
+                (&None, _, Some(address)) => address.to_string(),
+                (&None, _, None) => "<pending>".to_owned(),
+            }
+        };
+        let style = if bp.enabled {
+            StyleModifier::new()
+        } else {
+            StyleModifier::new().fg_color(Color::LightBlack)
+        };
+        ListRow::new(vec![
+            number,
+            bp.kind.description().to_owned(),
+            if bp.enabled { "y" } else { "n" }.to_owned(),
+            bp.hit_count.to_string(),
+            bp.ignore_count.to_string(),
+            bp.condition.clone().unwrap_or_default(),
+            bp.commands.join("; "),
+            location,
+        ])
+        .style(style)
+    }
+
+    fn update_rows(&mut self, p: ::UpdateParameters) {
+        let selected_number = self.selected_number();
+        let mut breakpoints = p.gdb.breakpoints.values().collect::<Vec<&BreakPoint>>();
+        breakpoints.sort_by_key(|bp| bp.number);
+        self.numbers = breakpoints.iter().map(|bp| bp.number).collect();
+        self.list
+            .set_rows(breakpoints.into_iter().map(Self::format_row).collect());
+        if let Some(number) = selected_number {
+            if let Some(row) = self.numbers.iter().position(|&n| n == number) {
+                self.list.select(row);
+            }
+        }
+    }
+
+    fn selected_number(&self) -> Option<BreakPointNumber> {
+        self.list
+            .selected()
+            .and_then(|row| self.numbers.get(row).cloned())
+    }
+
+    pub fn update_after_event(&mut self, p: ::UpdateParameters) {
+        if p.gdb.breakpoints.last_change > self.last_bp_update {
+            self.update_rows(p);
+            self.last_bp_update = p.gdb.breakpoints.last_change;
+        }
+    }
+
+    fn toggle_enabled(&mut self, p: ::UpdateParameters) {
+        if let Some(number) = self.selected_number() {
+            let enabled = match p.gdb.breakpoints.get(&number) {
+                Some(bp) => bp.enabled,
+                None => return,
+            };
+            if let Err(e) = p.gdb.set_breakpoint_enabled(number, !enabled) {
+                p.message_sink
+                    .send(format!("Cannot modify breakpoint {}: {}", number, e));
+            }
+        }
+    }
+
+    fn delete(&mut self, p: ::UpdateParameters) {
+        if let Some(number) = self.selected_number() {
+            if number.minor.is_some() {
+                p.message_sink.send(format!(
+                    "Cannot delete location {}: Disable it or delete breakpoint {} instead.",
+                    number, number.major
+                ));
+            } else if let Err(e) = p.gdb.delete_breakpoints(Some(number).into_iter()) {
+                p.message_sink
+                    .send(format!("Cannot delete breakpoint {}: {}", number, e));
+            }
+        }
+    }
+
+    fn show_location(&mut self, p: ::UpdateParameters) {
+        if let Some(bp) = self
+            .selected_number()
+            .and_then(|number| p.gdb.breakpoints.get(&number))
+        {
+            p.tui_events
+                .send(TuiEvent::ShowLocation(bp.src_pos.clone(), bp.address));
+        }
+    }
+
+    fn start_editing(&mut self, property: Property, p: ::UpdateParameters) {
+        if let Some(number) = self.selected_number() {
+            // Conditions, ignore counts and commands belong to the breakpoint, not its locations.
+            let number = BreakPointNumber {
+                major: number.major,
+                minor: None,
+            };
+            let value = p
+                .gdb
+                .breakpoints
+                .get(&number)
+                .map(|bp| property.current_value(bp))
+                .unwrap_or_default();
+            self.open_editor(number, property, &value);
+        }
+    }
+
+    fn open_editor(&mut self, number: BreakPointNumber, property: Property, value: &str) {
+        self.prompt_line.set_prompt(property.prompt(number));
+        self.prompt_line.line.set(value);
+        self.editing = Some((number, property));
+    }
+
+    fn stop_editing(&mut self) {
+        self.editing = None;
+        self.prompt_line.finish_line();
+        self.prompt_line.set_prompt(IDLE_PROMPT.to_owned());
+    }
+
+    fn submit(&mut self, p: ::UpdateParameters) {
+        let (number, property) = if let Some(editing) = self.editing {
+            editing
+        } else {
+            return;
+        };
+        let line = self.prompt_line.active_line().trim().to_owned();
+        let result = match property {
+            Property::Condition => {
+                let condition = Some(line.as_str()).filter(|l| !l.is_empty());
+                p.gdb.set_breakpoint_condition(number, condition)
+            }
+            Property::IgnoreCount => match line.parse::<usize>() {
+                Ok(count) => p.gdb.set_breakpoint_ignore_count(number, count),
+                Err(_) => {
+                    p.message_sink
+                        .send(format!("Invalid ignore count: '{}'", line));
+                    return;
+                }
+            },
+            Property::Commands => {
+                let commands = line
+                    .split(';')
+                    .map(|c| c.trim())
+                    .filter(|c| !c.is_empty())
+                    .collect::<Vec<&str>>();
+                p.gdb.set_breakpoint_commands(number, &commands)
+            }
+        };
+        match result {
+            Ok(()) => self.stop_editing(),
+            Err(e) => p
+                .message_sink
+                .send(format!("Cannot modify breakpoint {}: {}", number, e)),
+        }
+    }
+}
+
+impl<'a> Widget for BreakpointList<'a> {
+    fn space_demand(&self) -> Demand2D {
+        let widgets: Vec<&Widget> = vec![&self.list, &self.prompt_line];
+        self.layout.space_demand(widgets.as_slice())
+    }
+    fn draw(&self, window: Window, hints: RenderingHints) {
+        self.layout.draw(
+            window,
+            &[
+                (&self.list, hints.active(self.editing.is_none())),
+                (&self.prompt_line, hints.active(self.editing.is_some())),
+            ],
+        )
+    }
+}
+
+impl<'a> Container<::UpdateParametersStruct> for BreakpointList<'a> {
+    fn input(&mut self, input: Input, p: ::UpdateParameters) -> Option<Input> {
+        let keymap = self.keymap;
+        if self.editing.is_some() {
+            input
+                .chain(keymap.on(Action::PromptSubmit, || self.submit(p)))
+                .chain(keymap.on(Action::PromptCancel, || self.stop_editing()))
+                .chain(
+                    EditBehavior::new(&mut self.prompt_line)
+                        .left_on(Key::Left)
+                        .right_on(Key::Right)
+                        .delete_forwards_on(Key::Delete)
+                        .delete_backwards_on(Key::Backspace)
+                        .go_to_beginning_of_line_on(Key::Home)
+                        .go_to_end_of_line_on(Key::End),
+                )
+                .finish()
+        } else {
+            input
+                .chain(keymap.on(Action::ListUp, || self.list.select_previous()))
+                .chain(keymap.on(Action::ListDown, || self.list.select_next()))
+                .chain(keymap.on(Action::ListToBeginning, || self.list.select_first()))
+                .chain(keymap.on(Action::ListToEnd, || self.list.select_last()))
+                .chain(keymap.on(Action::BreakpointsToggleEnabled, || {
+                    self.toggle_enabled(p)
+                }))
+                .chain(keymap.on(Action::BreakpointsDelete, || self.delete(p)))
+                .chain(keymap.on(Action::BreakpointsShowLocation, || {
+                    self.show_location(p)
+                }))
+                .chain(keymap.on(Action::BreakpointsEditCondition, || {
+                    self.start_editing(Property::Condition, p)
+                }))
+                .chain(keymap.on(Action::BreakpointsEditIgnoreCount, || {
+                    self.start_editing(Property::IgnoreCount, p)
+                }))
+                .chain(keymap.on(Action::BreakpointsEditCommands, || {
+                    self.start_editing(Property::Commands, p)
+                }))
+                .finish()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use gdbmi::output::JsonValue;
+
+    fn breakpoint(bkpt: &str) -> BreakPoint {
+        match ::json::parse(bkpt).unwrap() {
+            JsonValue::Object(obj) => BreakPoint::from_json(&obj).unwrap(),
+            other => panic!("Not an object: {}", other),
+        }
+    }
+
+    #[test]
+    fn test_editor_starts_with_current_value() {
+        let bp = breakpoint(
+            r#"{"number":"1","type":"breakpoint","disp":"keep","enabled":"y","cond":"i == 3","ignore":"2","script":["print i","bt"],"original-location":"main.c:3"}"#,
+        );
+        let keymap = Keymap::default();
+        let mut list = BreakpointList::new(&keymap);
+        for &(property, value) in &[
+            (Property::Condition, "i == 3"),
+            (Property::IgnoreCount, "2"),
+            (Property::Commands, "print i; bt"),
+        ] {
+            list.open_editor(bp.number, property, &property.current_value(&bp));
+            assert_eq!(list.prompt_line.active_line(), value);
+            assert_eq!(list.editing, Some((bp.number, property)));
+            list.stop_editing();
+            assert_eq!(list.prompt_line.active_line(), "");
+        }
+    }
+}
@@ -3,6 +3,14 @@ use gdbmi::output::{ResultClass, ResultRecord};
 use gdbmi::ExecuteError;
 
 use log::error;
+use tui::TuiEvent;
+
+// Commands (and their abbreviations) that run the program, which is impossible for a core dump.
+const EXECUTION_COMMANDS: &[&str] = &[
+    "!stop", "r", "run", "start", "starti", "c", "continue", "fg", "n", "next", "s", "step", "ni",
+    "nexti", "si", "stepi", "u", "until", "advance", "fin", "finish", "j", "jump", "call", "kill",
+    "signal", "queue-signal",
+];
 
 pub struct Command {
     cmd: Box<FnMut(::UpdateParameters) -> Result<(), ExecuteError>>,
@@ -95,6 +103,19 @@ impl CommandState {
         }
     }
 
+    // Ask whether gdb should be restarted after it exited unexpectedly. The respawn itself is
+    // performed by the main loop, which owns the channels of the gdb process.
+    pub fn confirm_respawn(p: ::UpdateParameters) -> Self {
+        p.message_sink.send(format!(
+            "gdb exited unexpectedly ({}). Respawn gdb? (y or n)",
+            p.gdb.unexpected_exit.as_ref().map(|s| s.as_str()).unwrap_or("unknown status")
+        ));
+        CommandState::WaitingForConfirmation(Command::new(Box::new(|p: ::UpdateParameters| {
+            p.gdb.respawn_requested = true;
+            Ok(())
+        })))
+    }
+
     fn dispatch_command(line: &str, p: ::UpdateParameters) -> Self {
         let mut cmd_split = line.split(' ');
         let cmd = if let Some(cmd) = cmd_split.next() {
@@ -102,10 +123,24 @@ impl CommandState {
         } else {
             return CommandState::Idle;
         };
-        let _arguments = cmd_split.collect::<Vec<_>>();
+        let arguments = cmd_split.filter(|a| !a.is_empty()).collect::<Vec<_>>();
+        if p.gdb.unexpected_exit.is_some() && cmd != "!respawn" {
+            p.message_sink
+                .send("gdb is not running. Use '!respawn' to start it again.");
+            return CommandState::Idle;
+        }
+        if p.gdb.core_dump.is_some() && EXECUTION_COMMANDS.contains(&cmd) {
+            p.message_sink.send(format!(
+                "'{}' is not available for core dumps: The program is not running.",
+                cmd
+            ));
+            return CommandState::Idle;
+        }
         match cmd {
             "!stop" => {
-                p.gdb.mi.interrupt_execution().expect("interrupted gdb");
+                if let Err(e) = p.gdb.mi.interrupt_execution() {
+                    p.message_sink.send(format!("Cannot interrupt gdb: {}", e));
+                }
                 // This does not always seem to unblock gdb, but only hang it
                 //gdb.execute(&MiCommand::exec_interrupt()).expect("Interrupt");
 
@@ -130,6 +165,41 @@ impl CommandState {
                     CommandState::Idle
                 }
             },
+            "!respawn" => {
+                if p.gdb.unexpected_exit.is_some() {
+                    Self::confirm_respawn(p)
+                } else {
+                    p.message_sink.send("gdb is still running.");
+                    CommandState::Idle
+                }
+            }
+            "!attach" => {
+                match arguments.first().map(|pid| pid.parse::<u32>()) {
+                    Some(Ok(pid)) => match p.gdb.attach(pid) {
+                        Ok(()) => p.message_sink.send(format!("Attached to process {}.", pid)),
+                        Err(e) => p.message_sink.send(e.to_string()),
+                    },
+                    Some(Err(_)) => p
+                        .message_sink
+                        .send(format!("'{}' is not a process id.", arguments[0])),
+                    None => p.tui_events.send(TuiEvent::ShowAttachPicker),
+                }
+                CommandState::Idle
+            }
+            "!detach" => {
+                match p.gdb.detach() {
+                    Ok(()) => p.message_sink.send("Detached."),
+                    Err(e) => p.message_sink.send(format!("Cannot detach: {}", e)),
+                }
+                CommandState::Idle
+            }
+            "!reconnect" => {
+                match p.gdb.reconnect_remote() {
+                    Ok(()) => p.message_sink.send("Reconnected to the remote target."),
+                    Err(e) => p.message_sink.send(format!("Cannot reconnect: {}", e)),
+                }
+                CommandState::Idle
+            }
             "q" => {
                 Self::ask_if_session_active(Command::from_mi(MiCommand::exit()), "Quit anyway?", p)
             }
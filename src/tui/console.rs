@@ -1,8 +1,10 @@
+use gdb::ExecutionDirection;
+use keymap::{Action, Keymap};
 use tui::commands::CommandState;
 
 use unsegen::base::{GraphemeCluster, Window};
 use unsegen::container::Container;
-use unsegen::input::{EditBehavior, Input, Key, ScrollBehavior};
+use unsegen::input::{EditBehavior, Input, Key, ScrollBehavior, Scrollable};
 use unsegen::widget::builtin::{LogViewer, PromptLine};
 use unsegen::widget::{Demand2D, RenderingHints, SeparatingStyle, VerticalLayout, Widget};
 
@@ -12,33 +14,39 @@ enum GDBState {
     Stopped,
 }
 
-pub struct Console {
+pub struct Console<'a> {
+    keymap: &'a Keymap,
     gdb_log: LogViewer,
     prompt_line: PromptLine,
     layout: VerticalLayout,
     last_gdb_state: GDBState,
+    last_direction: ExecutionDirection,
     command_state: CommandState,
 }
 
 static STOPPED_PROMPT: &'static str = "(gdb) ";
 static RUNNING_PROMPT: &'static str = "(↻↻↻) ";
+static REVERSE_STOPPED_PROMPT: &'static str = "(gdb ◀) ";
+static REVERSE_RUNNING_PROMPT: &'static str = "(↺↺↺) ";
 
-impl Console {
-    pub fn new() -> Self {
+impl<'a> Console<'a> {
+    pub fn new(keymap: &'a Keymap) -> Self {
         Console {
+            keymap: keymap,
             gdb_log: LogViewer::new(),
             prompt_line: PromptLine::with_prompt(STOPPED_PROMPT.into()),
             layout: VerticalLayout::new(SeparatingStyle::Draw(
                 GraphemeCluster::try_from('=').unwrap(),
             )),
             last_gdb_state: GDBState::Stopped,
+            last_direction: ExecutionDirection::Forward,
             command_state: CommandState::Idle,
         }
     }
 
-    pub fn display_messages(&mut self, sink: &mut ::MessageSink) {
+    pub fn display_messages(&mut self, messages: &[String]) {
         use std::fmt::Write;
-        for msg in sink.drain_messages() {
+        for msg in messages {
             writeln!(self.gdb_log, "{}", msg).expect("Write Message");
         }
     }
@@ -57,22 +65,35 @@ impl Console {
         self.write_to_gdb_log(format!("{}{}\n", STOPPED_PROMPT, line));
         self.command_state.handle_input_line(&line, p);
     }
+
+    // Ask the user whether gdb should be restarted, e.g., after it crashed.
+    pub fn ask_for_respawn(&mut self, p: ::UpdateParameters) {
+        self.command_state = CommandState::confirm_respawn(p);
+    }
+
     pub fn update_after_event(&mut self, p: ::UpdateParameters) {
-        if p.gdb.mi.is_running() {
-            if self.last_gdb_state != GDBState::Running {
-                self.last_gdb_state = GDBState::Running;
-                self.prompt_line.set_prompt(RUNNING_PROMPT.to_owned());
-            }
+        let state = if p.gdb.mi.accepts_commands() {
+            GDBState::Stopped
         } else {
-            if self.last_gdb_state != GDBState::Stopped {
-                self.last_gdb_state = GDBState::Stopped;
-                self.prompt_line.set_prompt(STOPPED_PROMPT.to_owned());
-            }
+            GDBState::Running
+        };
+        let direction = p.gdb.execution_direction;
+        if state != self.last_gdb_state || direction != self.last_direction {
+            self.last_gdb_state = state;
+            self.last_direction = direction;
+            // Make it obvious that the program moves (or was last moved) backwards.
+            let prompt = match (state, direction) {
+                (GDBState::Stopped, ExecutionDirection::Forward) => STOPPED_PROMPT,
+                (GDBState::Running, ExecutionDirection::Forward) => RUNNING_PROMPT,
+                (GDBState::Stopped, ExecutionDirection::Reverse) => REVERSE_STOPPED_PROMPT,
+                (GDBState::Running, ExecutionDirection::Reverse) => REVERSE_RUNNING_PROMPT,
+            };
+            self.prompt_line.set_prompt(prompt.to_owned());
         }
     }
 }
 
-impl Widget for Console {
+impl<'a> Widget for Console<'a> {
     fn space_demand(&self) -> Demand2D {
         let widgets: Vec<&Widget> = vec![&self.gdb_log, &self.prompt_line];
         self.layout.space_demand(widgets.as_slice())
@@ -84,8 +105,9 @@ impl Widget for Console {
         )
     }
 }
-impl Container<::UpdateParametersStruct> for Console {
+impl<'a> Container<::UpdateParametersStruct> for Console<'a> {
     fn input(&mut self, input: Input, p: ::UpdateParameters) -> Option<Input> {
+        let keymap = self.keymap;
         input
             .chain((Key::Char('\n'), || self.handle_newline(p)))
             .chain(
@@ -101,16 +123,23 @@ impl Container<::UpdateParametersStruct> for Console {
                     .clear_on(Key::Ctrl('c')),
             )
             .chain(ScrollBehavior::new(&mut self.prompt_line).to_end_on(Key::Ctrl('r')))
-            .chain((Key::Ctrl('c'), || {
-                p.gdb.mi.interrupt_execution().expect("interrupted gdb")
+            .chain(keymap.on(Action::ConsoleInterrupt, || {
+                if let Err(e) = p.gdb.mi.interrupt_execution() {
+                    p.message_sink.send(format!("Cannot interrupt gdb: {}", e));
+                }
+            }))
+            .chain(keymap.on(Action::ConsoleScrollDown, || {
+                let _ = self.gdb_log.scroll_forwards();
+            }))
+            .chain(keymap.on(Action::ConsoleScrollUp, || {
+                let _ = self.gdb_log.scroll_backwards();
+            }))
+            .chain(keymap.on(Action::ConsoleToBeginning, || {
+                let _ = self.gdb_log.scroll_to_beginning();
+            }))
+            .chain(keymap.on(Action::ConsoleToEnd, || {
+                let _ = self.gdb_log.scroll_to_end();
             }))
-            .chain(
-                ScrollBehavior::new(&mut self.gdb_log)
-                    .forwards_on(Key::PageDown)
-                    .backwards_on(Key::PageUp)
-                    .to_beginning_on(Key::Ctrl('b'))
-                    .to_end_on(Key::Ctrl('e')),
-            )
             .finish()
     }
 }
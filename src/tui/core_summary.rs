@@ -0,0 +1,147 @@
+use gdb::StackFrame;
+use keymap::{Action, Keymap};
+use log::warn;
+use tui::select_list::{ListRow, SelectList};
+use tui::TuiEvent;
+
+use unsegen::base::{Color, StyleModifier, Window};
+use unsegen::container::Container;
+use unsegen::input::Input;
+use unsegen::widget::{Demand2D, RenderingHints, Widget};
+
+// Only the innermost frames of each thread are listed to keep the summary readable.
+const MAX_FRAMES: u64 = 64;
+
+// Post-mortem overview of a core dump: The signal that terminated the program and the backtraces
+// of all threads.
+pub struct CoreSummary<'a> {
+    keymap: &'a Keymap,
+    list: SelectList,
+    // Thread id and frame level of each row of the list (None for signal and thread rows)
+    frames: Vec<Option<(u64, u64)>>,
+}
+
+impl<'a> CoreSummary<'a> {
+    pub fn new(keymap: &'a Keymap) -> Self {
+        CoreSummary {
+            keymap: keymap,
+            list: SelectList::new(vec!["Thread", "#", "Function", "Location"]),
+            frames: Vec::new(),
+        }
+    }
+
+    fn format_frame(frame: &StackFrame) -> ListRow {
+        let location = match (&frame.src_pos, &frame.library, frame.address) {
+            (&Some(ref pos), _, _) => format!("{}:{}", pos.file.to_string_lossy(), pos.line),
+            (&None, &Some(ref library), _) => library.clone(),
+            (&None, &None, Some(address)) => address.to_string(),
+            (&None, &None, None) => String::new(),
+        };
+        ListRow::new(vec![
+            String::new(),
+            frame.level.to_string(),
+            frame.function.clone().unwrap_or_else(|| "??".to_owned()),
+            location,
+        ])
+    }
+
+    // Collect the backtraces of all threads, e.g., after a core file has been loaded.
+    pub fn update(&mut self, p: ::UpdateParameters) {
+        let description = match p.gdb.core_dump {
+            Some(ref core) => core.description(),
+            None => return,
+        };
+        let mut rows = vec![ListRow::new(vec![
+            "Signal".to_owned(),
+            String::new(),
+            description,
+            String::new(),
+        ])
+        .style(StyleModifier::new().fg_color(Color::Red).bold(true))];
+        self.frames = vec![None];
+
+        let current = p.gdb.threads.current;
+        let threads = p
+            .gdb
+            .threads
+            .values()
+            .map(|thread| {
+                let name = thread
+                    .name
+                    .as_ref()
+                    .or(thread.target_id.as_ref())
+                    .cloned()
+                    .unwrap_or_default();
+                (thread.id, name)
+            })
+            .collect::<Vec<_>>();
+        for (id, name) in threads {
+            let marker = if current == Some(id) { "* " } else { "  " };
+            rows.push(
+                ListRow::new(vec![
+                    format!("{}{}", marker, id),
+                    String::new(),
+                    name,
+                    String::new(),
+                ])
+                .style(StyleModifier::new().bold(true)),
+            );
+            self.frames.push(None);
+            match p.gdb.get_thread_backtrace(Some(id), MAX_FRAMES) {
+                Ok(frames) => {
+                    for frame in frames {
+                        rows.push(Self::format_frame(&frame));
+                        self.frames.push(Some((id, frame.level)));
+                    }
+                }
+                Err(e) => warn!("Failed to get backtrace of thread {}: {:?}", id, e),
+            }
+        }
+        self.list.set_rows(rows);
+        self.list.select_first();
+    }
+
+    fn select_frame(&mut self, p: ::UpdateParameters) {
+        let (thread, level) = match self
+            .list
+            .selected()
+            .and_then(|row| self.frames.get(row).cloned())
+        {
+            Some(Some(frame)) => frame,
+            _ => return,
+        };
+        let result = p
+            .gdb
+            .select_thread(thread)
+            .and_then(|_| p.gdb.select_frame(level));
+        match result {
+            Ok(frame) => p.tui_events.send(TuiEvent::ThreadSelected(frame)),
+            Err(e) => p.message_sink.send(format!(
+                "Cannot select frame {} of thread {}: {:?}",
+                level, thread, e
+            )),
+        }
+    }
+}
+
+impl<'a> Widget for CoreSummary<'a> {
+    fn space_demand(&self) -> Demand2D {
+        self.list.space_demand()
+    }
+    fn draw(&self, window: Window, hints: RenderingHints) {
+        self.list.draw(window, hints)
+    }
+}
+
+impl<'a> Container<::UpdateParametersStruct> for CoreSummary<'a> {
+    fn input(&mut self, input: Input, p: ::UpdateParameters) -> Option<Input> {
+        let keymap = self.keymap;
+        input
+            .chain(keymap.on(Action::ListUp, || self.list.select_previous()))
+            .chain(keymap.on(Action::ListDown, || self.list.select_next()))
+            .chain(keymap.on(Action::ListToBeginning, || self.list.select_first()))
+            .chain(keymap.on(Action::ListToEnd, || self.list.select_last()))
+            .chain(keymap.on(Action::CoreSummarySelectFrame, || self.select_frame(p)))
+            .finish()
+    }
+}
@@ -1,30 +1,44 @@
 use self::json_ext::JsonValue;
 use gdb_expression_parsing::parse_gdb_value;
-use gdbmi::commands::MiCommand;
+use gdbmi::commands::{MiCommand, WatchMode};
 use gdbmi::output::ResultClass;
-use gdbmi::ExecuteError;
+use gdbmi::{AsyncResult, Token};
+use keymap::{Action, Keymap};
+use std::time::Duration;
 use unsegen::base::{Color, GraphemeCluster, StyleModifier, Window};
 use unsegen::container::Container;
-use unsegen::input::{EditBehavior, Input, Key, NavigateBehavior, ScrollBehavior};
+use unsegen::input::{EditBehavior, Input, Key, Navigatable, ScrollBehavior};
 use unsegen::widget::builtin::{Column, LineEdit, Table, TableRow};
 use unsegen::widget::{Demand2D, RenderingHints, SeparatingStyle, Widget};
 use unsegen_jsonviewer::{json_ext, JsonViewer};
 
+// Evaluation of expressions can take very long (e.g., for huge arrays), but must not block the ui.
+const EVALUATION_TIMEOUT: Duration = Duration::from_secs(10);
+
 pub struct ExpressionRow {
     expression: LineEdit,
     result: JsonViewer,
+    pending_evaluation: Option<Token>,
+    // The expression that the result belongs to (None if it has to be evaluated again)
+    evaluated_expression: Option<String>,
 }
 impl ExpressionRow {
     fn new() -> Self {
         ExpressionRow {
             expression: LineEdit::new(),
             result: JsonViewer::new(&JsonValue::Null),
+            pending_evaluation: None,
+            evaluated_expression: None,
         }
     }
 
     fn is_empty(&self) -> bool {
         self.expression.get().is_empty()
     }
+
+    fn is_outdated(&self) -> bool {
+        self.evaluated_expression.as_ref().map(|e| e.as_str()) != Some(self.expression.get())
+    }
 }
 impl TableRow for ExpressionRow {
     const COLUMNS: &'static [Column<ExpressionRow>] = &[
@@ -79,19 +93,23 @@ impl TableRow for ExpressionRow {
     ];
 }
 
-pub struct ExpressionTable {
+pub struct ExpressionTable<'a> {
+    keymap: &'a Keymap,
     table: Table<ExpressionRow>,
 }
 
-impl ExpressionTable {
-    pub fn new() -> Self {
+impl<'a> ExpressionTable<'a> {
+    pub fn new(keymap: &'a Keymap) -> Self {
         let row_sep_style =
             SeparatingStyle::AlternatingStyle(StyleModifier::new().bg_color(Color::Black));
         let col_sep_style = SeparatingStyle::Draw(GraphemeCluster::try_from('│').unwrap());
         let focused_style = StyleModifier::new().bold(true);
         let mut table = Table::new(row_sep_style, col_sep_style, focused_style);
         table.rows_mut().push(ExpressionRow::new()); //Invariant: always at least one line
-        ExpressionTable { table: table }
+        ExpressionTable {
+            keymap: keymap,
+            table: table,
+        }
     }
     fn shrink_to_fit(&mut self) {
         let begin_of_empty_range = {
@@ -108,40 +126,96 @@ impl ExpressionTable {
         rows.push(ExpressionRow::new());
     }
 
+    // Evaluate all expressions again, e.g., after the program stopped.
     pub fn update_results(&mut self, p: ::UpdateParameters) {
         for row in self.table.rows_mut().iter_mut() {
-            let expr = row.expression.get().to_owned();
-            let result = if expr.is_empty() {
-                JsonValue::Null
-            } else {
-                match p.gdb.mi.execute(MiCommand::data_evaluate_expression(expr)) {
-                    Ok(res) => match res.class {
-                        ResultClass::Error => res.results["msg"].clone(),
-                        ResultClass::Done => {
-                            let to_parse = res.results["value"].as_str().expect("value present");
-                            match parse_gdb_value(to_parse) {
-                                Ok(p) => p,
-                                Err(_) => {
-                                    JsonValue::String(format!("*Error parsing*: {}", to_parse))
-                                }
-                            }
-                        }
-                        other => panic!("unexpected result class: {:?}", other),
+            row.evaluated_expression = None;
+        }
+        self.evaluate_outdated_rows(p);
+    }
+
+    // Evaluate the rows whose expression has changed since the last evaluation. A running program
+    // is interrupted only once for all of them.
+    fn evaluate_outdated_rows(&mut self, p: ::UpdateParameters) {
+        let mut rows = self.table.rows_mut();
+        for row in rows.iter_mut().filter(|row| row.is_outdated()) {
+            if let Some(token) = row.pending_evaluation.take() {
+                p.gdb.mi.cancel(token);
+            }
+            if row.is_empty() {
+                row.result.update(&JsonValue::Null);
+                row.evaluated_expression = Some(String::new());
+            }
+        }
+        if !rows.iter().any(|row| row.is_outdated()) {
+            return;
+        }
+        let result = p.gdb.with_stopped_program(|mi| {
+            for row in rows.iter_mut().filter(|row| row.is_outdated()) {
+                let expr = row.expression.get().to_owned();
+                let token = mi.execute_async(
+                    MiCommand::data_evaluate_expression(expr.clone()),
+                    Some(EVALUATION_TIMEOUT),
+                )?;
+                row.pending_evaluation = Some(token);
+                row.evaluated_expression = Some(expr);
+            }
+            Ok(())
+        });
+        if result.is_err() {
+            // The remaining rows stay outdated and are evaluated again on the next update (e.g.,
+            // once gdb has been respawned). Until then, their old results must not be shown.
+            for row in rows.iter_mut().filter(|row| row.is_outdated()) {
+                row.result
+                    .update(&JsonValue::String("*Not evaluated*".to_owned()));
+            }
+        }
+    }
+
+    pub fn handle_async_result(&mut self, token: Token, result: &AsyncResult) {
+        for row in self.table.rows_mut().iter_mut() {
+            if row.pending_evaluation != Some(token) {
+                continue;
+            }
+            row.pending_evaluation = None;
+            let result = match result {
+                AsyncResult::Done(res) => match res.class {
+                    ResultClass::Error => res.results["msg"].clone(),
+                    ResultClass::Done => match res.results["value"].as_str() {
+                        Some(to_parse) => match parse_gdb_value(to_parse) {
+                            Ok(p) => p,
+                            Err(_) => JsonValue::String(format!("*Error parsing*: {}", to_parse)),
+                        },
+                        None => JsonValue::String(format!(
+                            "*Unexpected response*: {}",
+                            res.results.dump()
+                        )),
                     },
-                    Err(ExecuteError::Busy) => {
-                        return;
-                    }
-                    Err(ExecuteError::Quit) => {
-                        panic!("GDB quit!");
-                    }
-                }
+                    other => JsonValue::String(format!("*Unexpected result class*: {:?}", other)),
+                },
+                AsyncResult::TimedOut => JsonValue::String("*Evaluation timed out*".to_owned()),
             };
             row.result.update(&result);
         }
     }
+
+    fn watch_current_expression(&mut self, mode: WatchMode, p: ::UpdateParameters) {
+        let expression = match self.table.current_row_mut() {
+            Some(row) if !row.is_empty() => row.expression.get().to_owned(),
+            _ => return,
+        };
+        match p.gdb.insert_watchpoint(&expression, mode) {
+            Ok(number) => p
+                .message_sink
+                .send(format!("Watchpoint {}: {}", number, expression)),
+            Err(e) => p
+                .message_sink
+                .send(format!("Cannot watch '{}': {}", expression, e)),
+        }
+    }
 }
 
-impl Widget for ExpressionTable {
+impl<'a> Widget for ExpressionTable<'a> {
     fn space_demand(&self) -> Demand2D {
         self.table.space_demand()
     }
@@ -150,28 +224,44 @@ impl Widget for ExpressionTable {
     }
 }
 
-impl Container<::UpdateParametersStruct> for ExpressionTable {
+impl<'a> Container<::UpdateParametersStruct> for ExpressionTable<'a> {
     fn input(&mut self, input: Input, p: ::UpdateParameters) -> Option<Input> {
+        let keymap = self.keymap;
         let res = input
             .chain(|i: Input| match i.event {
                 _ => Some(i),
             })
-            .chain(
-                NavigateBehavior::new(&mut self.table) //TODO: Fix this properly in lineedit
-                    .down_on(Key::Char('\n')),
-            )
+            //TODO: Fix this properly in lineedit
+            .chain(keymap.on(Action::ExpressionTableNextRow, || {
+                let _ = self.table.move_down();
+            }))
+            .chain(keymap.on(Action::ExpressionTableWatch, || {
+                self.watch_current_expression(WatchMode::Write, p)
+            }))
+            .chain(keymap.on(Action::ExpressionTableReadWatch, || {
+                self.watch_current_expression(WatchMode::Read, p)
+            }))
+            .chain(keymap.on(Action::ExpressionTableAccessWatch, || {
+                self.watch_current_expression(WatchMode::Access, p)
+            }))
             .chain(self.table.current_cell_behavior())
-            .chain(
-                NavigateBehavior::new(&mut self.table)
-                    .up_on(Key::Up)
-                    .down_on(Key::Down)
-                    .left_on(Key::Left)
-                    .right_on(Key::Right),
-            )
+            .chain(keymap.on(Action::ExpressionTableUp, || {
+                let _ = self.table.move_up();
+            }))
+            .chain(keymap.on(Action::ExpressionTableDown, || {
+                let _ = self.table.move_down();
+            }))
+            .chain(keymap.on(Action::ExpressionTableLeft, || {
+                let _ = self.table.move_left();
+            }))
+            .chain(keymap.on(Action::ExpressionTableRight, || {
+                let _ = self.table.move_right();
+            }))
             .finish();
 
         self.shrink_to_fit();
-        self.update_results(p);
+        // Only rows whose expression has been edited are evaluated again.
+        self.evaluate_outdated_rows(p);
         res
     }
 }
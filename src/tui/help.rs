@@ -0,0 +1,168 @@
+use keymap::{format_key, Action, Keymap};
+use std::cell::Cell;
+use unsegen::base::{Cursor, StyleModifier, Window};
+use unsegen::input::{Input, Key};
+use unsegen::widget::{text_width, Demand, Demand2D, RenderingHints, Widget};
+
+// Title and empty line above the (scrollable) list of actions
+const HEADER_LINES: usize = 2;
+
+// Overview of all actions and the keys they are currently bound to.
+pub struct HelpOverlay<'a> {
+    keymap: &'a Keymap,
+    // Index of the first visible action
+    scroll: usize,
+    // Number of actions that fit into the window at the last draw
+    visible_actions: Cell<usize>,
+}
+
+impl<'a> HelpOverlay<'a> {
+    pub fn new(keymap: &'a Keymap) -> Self {
+        HelpOverlay {
+            keymap: keymap,
+            scroll: 0,
+            visible_actions: Cell::new(Action::ALL.len()),
+        }
+    }
+
+    fn formatted_keys(&self, action: Action) -> String {
+        let keys = self.keymap.keys(action);
+        if keys.is_empty() {
+            "(unbound)".to_owned()
+        } else {
+            keys.iter().map(format_key).collect::<Vec<_>>().join(", ")
+        }
+    }
+
+    fn max_scroll(&self) -> usize {
+        Action::ALL
+            .len()
+            .checked_sub(self.visible_actions.get())
+            .unwrap_or(0)
+    }
+
+    fn scroll_by(&mut self, lines: isize) {
+        self.scroll = if lines < 0 {
+            self.scroll.checked_sub(-lines as usize).unwrap_or(0)
+        } else {
+            (self.scroll + lines as usize).min(self.max_scroll())
+        };
+    }
+
+    // Scroll through the actions. All other inputs are returned (and close the overlay).
+    pub fn input(&mut self, input: Input) -> Option<Input> {
+        let keymap = self.keymap;
+        let page = self.visible_actions.get().max(1) as isize;
+        input
+            .chain(keymap.on(Action::ListUp, || self.scroll_by(-1)))
+            .chain(keymap.on(Action::ListDown, || self.scroll_by(1)))
+            .chain(keymap.on(Action::ListToBeginning, || self.scroll = 0))
+            .chain(keymap.on(Action::ListToEnd, || self.scroll = self.max_scroll()))
+            .chain(|i: Input| {
+                if i.matches(Key::PageUp) {
+                    self.scroll_by(-page);
+                    None
+                } else if i.matches(Key::PageDown) {
+                    self.scroll_by(page);
+                    None
+                } else {
+                    Some(i)
+                }
+            })
+            .finish()
+    }
+}
+
+impl<'a> Widget for HelpOverlay<'a> {
+    fn space_demand(&self) -> Demand2D {
+        Demand2D {
+            width: Demand::at_least(1),
+            height: Demand::at_least(1),
+        }
+    }
+    fn draw(&self, mut window: Window, _: RenderingHints) {
+        use std::fmt::Write;
+        let name_width = Action::ALL
+            .iter()
+            .map(|a| text_width(a.name()).raw_value() as usize)
+            .max()
+            .unwrap_or(0);
+        let key_width = Action::ALL
+            .iter()
+            .map(|&a| text_width(&self.formatted_keys(a)).raw_value() as usize)
+            .max()
+            .unwrap_or(0);
+        let visible_actions = (window.get_height().raw_value() as usize)
+            .checked_sub(HEADER_LINES)
+            .unwrap_or(0);
+        self.visible_actions.set(visible_actions);
+        // The window may have grown since the last input.
+        let first_action = self.scroll.min(self.max_scroll());
+
+        let mut c = Cursor::new(&mut window);
+        {
+            let mut c = c.save().style_modifier();
+            c.set_style_modifier(StyleModifier::new().bold(true));
+            let _ = write!(
+                c,
+                "Key bindings ({}-{} of {}, Up/Down/PageUp/PageDown: scroll, any other key: close)",
+                (first_action + 1).min(Action::ALL.len()),
+                (first_action + visible_actions).min(Action::ALL.len()),
+                Action::ALL.len()
+            );
+        }
+        c.wrap_line();
+        c.wrap_line();
+        for &action in Action::ALL.iter().skip(first_action).take(visible_actions) {
+            let _ = write!(
+                c,
+                "{:name_width$}  {:key_width$}  {}",
+                action.name(),
+                self.formatted_keys(action),
+                action.description(),
+                name_width = name_width,
+                key_width = key_width
+            );
+            c.wrap_line();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use unsegen::input::Event;
+
+    fn press(help: &mut HelpOverlay, key: Key) -> Option<Input> {
+        help.input(Input {
+            event: Event::Key(key),
+            raw: Vec::new(),
+        })
+    }
+
+    #[test]
+    fn test_scroll() {
+        let keymap = Keymap::default();
+        let mut help = HelpOverlay::new(&keymap);
+        help.visible_actions.set(10);
+        let max_scroll = Action::ALL.len() - 10;
+
+        assert!(press(&mut help, Key::Up).is_none());
+        assert_eq!(help.scroll, 0);
+        assert!(press(&mut help, Key::Down).is_none());
+        assert_eq!(help.scroll, 1);
+        assert!(press(&mut help, Key::PageDown).is_none());
+        assert_eq!(help.scroll, 11);
+        assert!(press(&mut help, Key::End).is_none());
+        assert_eq!(help.scroll, max_scroll);
+        assert!(press(&mut help, Key::Down).is_none());
+        assert_eq!(help.scroll, max_scroll);
+        assert!(press(&mut help, Key::PageUp).is_none());
+        assert_eq!(help.scroll, max_scroll - 10);
+        assert!(press(&mut help, Key::Home).is_none());
+        assert_eq!(help.scroll, 0);
+
+        // Everything else closes the overlay
+        assert!(press(&mut help, Key::Char('q')).is_some());
+    }
+}
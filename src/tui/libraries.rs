@@ -0,0 +1,96 @@
+use gdb::Library;
+use keymap::{Action, Keymap};
+use tui::select_list::{ListRow, SelectList};
+
+use unsegen::base::{Color, StyleModifier, Window};
+use unsegen::container::Container;
+use unsegen::input::Input;
+use unsegen::widget::{Demand2D, RenderingHints, Widget};
+
+pub struct LibraryList<'a> {
+    keymap: &'a Keymap,
+    list: SelectList,
+    // Target name of each row of the list
+    names: Vec<String>,
+    last_library_update: ::std::time::Instant,
+}
+
+impl<'a> LibraryList<'a> {
+    pub fn new(keymap: &'a Keymap) -> Self {
+        LibraryList {
+            keymap: keymap,
+            list: SelectList::new(vec!["Library", "Symbols", "Address range", "Inferior"]),
+            names: Vec::new(),
+            last_library_update: ::std::time::Instant::now(),
+        }
+    }
+
+    fn format_row(library: &Library) -> ListRow {
+        let range = match library.ranges.first() {
+            Some(&(from, to)) if library.ranges.len() > 1 => {
+                format!("{}-{} (+{})", from, to, library.ranges.len() - 1)
+            }
+            Some(&(from, to)) => format!("{}-{}", from, to),
+            None => String::new(),
+        };
+        let (symbols, style) = if library.symbols_loaded {
+            ("loaded", StyleModifier::new())
+        } else {
+            ("no", StyleModifier::new().fg_color(Color::LightBlack))
+        };
+        ListRow::new(vec![
+            library.host_name.clone(),
+            symbols.to_owned(),
+            range,
+            library.thread_group.clone().unwrap_or_default(),
+        ])
+        .style(style)
+    }
+
+    pub fn update_after_event(&mut self, p: ::UpdateParameters) {
+        if p.gdb.libraries.last_change > self.last_library_update {
+            self.names = p
+                .gdb
+                .libraries
+                .iter()
+                .map(|l| l.target_name.clone())
+                .collect();
+            self.list
+                .set_rows(p.gdb.libraries.iter().map(Self::format_row).collect());
+            self.last_library_update = p.gdb.libraries.last_change;
+        }
+    }
+
+    fn load_symbols(&mut self, p: ::UpdateParameters) {
+        if let Some(name) = self.list.selected().and_then(|row| self.names.get(row)) {
+            match p.gdb.load_library_symbols(name) {
+                Ok(()) => p.message_sink.send(format!("Loaded symbols of {}", name)),
+                Err(e) => p
+                    .message_sink
+                    .send(format!("Cannot load symbols of {}: {:?}", name, e)),
+            }
+        }
+    }
+}
+
+impl<'a> Widget for LibraryList<'a> {
+    fn space_demand(&self) -> Demand2D {
+        self.list.space_demand()
+    }
+    fn draw(&self, window: Window, hints: RenderingHints) {
+        self.list.draw(window, hints)
+    }
+}
+
+impl<'a> Container<::UpdateParametersStruct> for LibraryList<'a> {
+    fn input(&mut self, input: Input, p: ::UpdateParameters) -> Option<Input> {
+        let keymap = self.keymap;
+        input
+            .chain(keymap.on(Action::ListUp, || self.list.select_previous()))
+            .chain(keymap.on(Action::ListDown, || self.list.select_next()))
+            .chain(keymap.on(Action::ListToBeginning, || self.list.select_first()))
+            .chain(keymap.on(Action::ListToEnd, || self.list.select_last()))
+            .chain(keymap.on(Action::LibrariesLoadSymbols, || self.load_symbols(p)))
+            .finish()
+    }
+}
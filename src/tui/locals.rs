@@ -0,0 +1,165 @@
+use self::json_ext::JsonValue;
+use gdb::Variable;
+use gdb_expression_parsing::parse_gdb_value;
+use keymap::{Action, Keymap};
+use log::debug;
+use unsegen::base::{Color, GraphemeCluster, StyleModifier, Window};
+use unsegen::container::Container;
+use unsegen::input::{Input, Key, Navigatable, ScrollBehavior};
+use unsegen::widget::builtin::{Column, LineLabel, Table, TableRow};
+use unsegen::widget::{Demand2D, RenderingHints, SeparatingStyle, Widget};
+use unsegen_jsonviewer::{json_ext, JsonViewer};
+
+pub struct VariableRow {
+    name: String,
+    label: LineLabel,
+    value: JsonViewer,
+}
+
+impl VariableRow {
+    fn new(variable: &Variable) -> Self {
+        let label = if variable.is_argument {
+            format!("{} (arg)", variable.name)
+        } else {
+            variable.name.clone()
+        };
+        VariableRow {
+            name: variable.name.clone(),
+            label: LineLabel::new(label),
+            value: JsonViewer::new(&Self::parse_value(variable)),
+        }
+    }
+
+    fn parse_value(variable: &Variable) -> JsonValue {
+        match variable.value {
+            Some(ref value) => match parse_gdb_value(value) {
+                Ok(v) => v,
+                Err(_) => JsonValue::String(format!("*Error parsing*: {}", value)),
+            },
+            None => JsonValue::Null,
+        }
+    }
+
+    fn placeholder() -> Self {
+        VariableRow {
+            name: String::new(),
+            label: LineLabel::new("(no variables)"),
+            value: JsonViewer::new(&JsonValue::Null),
+        }
+    }
+}
+
+impl TableRow for VariableRow {
+    const COLUMNS: &'static [Column<VariableRow>] = &[
+        Column {
+            access: |r| &r.label,
+            access_mut: |r| &mut r.label,
+            behavior: |_, input| Some(input),
+        },
+        Column {
+            access: |r| &r.value,
+            access_mut: |r| &mut r.value,
+            behavior: |r, input| {
+                input
+                    .chain(
+                        ScrollBehavior::new(&mut r.value)
+                            .forwards_on(Key::PageDown)
+                            .backwards_on(Key::PageUp)
+                            .forwards_on(Key::Down)
+                            .backwards_on(Key::Up)
+                            .to_beginning_on(Key::Home)
+                            .to_end_on(Key::End),
+                    )
+                    .chain(|evt: Input| {
+                        if evt.matches(Key::Char(' ')) {
+                            if r.value.toggle_active_element().is_ok() {
+                                None
+                            } else {
+                                Some(evt)
+                            }
+                        } else {
+                            Some(evt)
+                        }
+                    })
+                    .finish()
+            },
+        },
+    ];
+}
+
+pub struct LocalsTable<'a> {
+    keymap: &'a Keymap,
+    table: Table<VariableRow>,
+}
+
+impl<'a> LocalsTable<'a> {
+    pub fn new(keymap: &'a Keymap) -> Self {
+        let row_sep_style =
+            SeparatingStyle::AlternatingStyle(StyleModifier::new().bg_color(Color::Black));
+        let col_sep_style = SeparatingStyle::Draw(GraphemeCluster::try_from('│').unwrap());
+        let focused_style = StyleModifier::new().bold(true);
+        let mut table = Table::new(row_sep_style, col_sep_style, focused_style);
+        table.rows_mut().push(VariableRow::placeholder()); //Invariant: always at least one line
+        LocalsTable {
+            keymap: keymap,
+            table: table,
+        }
+    }
+
+    // Reload the variables of the selected frame, e.g., after a stop or a frame switch.
+    pub fn update(&mut self, p: ::UpdateParameters) {
+        let variables = match p.gdb.get_frame_variables() {
+            Ok(variables) => variables,
+            Err(e) => {
+                debug!("Failed to get frame variables: {:?}", e);
+                Vec::new()
+            }
+        };
+        let mut rows = self.table.rows_mut();
+        let same_variables = rows.len() == variables.len()
+            && rows.iter().zip(variables.iter()).all(|(r, v)| r.name == v.name);
+        if same_variables {
+            // Keep the state of the viewers (and highlight changes) if we are still in the same
+            // function.
+            for (row, variable) in rows.iter_mut().zip(variables.iter()) {
+                row.value.update(&VariableRow::parse_value(variable));
+            }
+        } else {
+            rows.clear();
+            rows.extend(variables.iter().map(VariableRow::new));
+            if rows.is_empty() {
+                rows.push(VariableRow::placeholder());
+            }
+        }
+    }
+}
+
+impl<'a> Widget for LocalsTable<'a> {
+    fn space_demand(&self) -> Demand2D {
+        self.table.space_demand()
+    }
+    fn draw(&self, window: Window, hints: RenderingHints) {
+        self.table.draw(window, hints);
+    }
+}
+
+impl<'a> Container<::UpdateParametersStruct> for LocalsTable<'a> {
+    fn input(&mut self, input: Input, _: ::UpdateParameters) -> Option<Input> {
+        let keymap = self.keymap;
+        input
+            .chain(self.table.current_cell_behavior())
+            .chain(keymap.on(Action::LocalsUp, || {
+                let _ = self.table.move_up();
+            }))
+            .chain(keymap.on(Action::LocalsDown, || {
+                let _ = self.table.move_down();
+            }))
+            .chain(keymap.on(Action::LocalsLeft, || {
+                let _ = self.table.move_left();
+            }))
+            .chain(keymap.on(Action::LocalsRight, || {
+                let _ = self.table.move_right();
+            }))
+            .finish()
+    }
+}
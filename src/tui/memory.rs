@@ -0,0 +1,359 @@
+use gdb::Address;
+use keymap::{Action, Keymap};
+use std::collections::HashMap;
+
+use unsegen::base::{Color, Cursor, GraphemeCluster, StyleModifier, Window};
+use unsegen::container::Container;
+use unsegen::input::{EditBehavior, Input, Key};
+use unsegen::widget::builtin::PromptLine;
+use unsegen::widget::{Demand, Demand2D, RenderingHints, SeparatingStyle, VerticalLayout, Widget};
+
+const BYTES_PER_LINE: usize = 16;
+const LINES: usize = 32;
+const LOADED_BYTES: usize = BYTES_PER_LINE * LINES;
+
+fn line_begin(address: Address) -> Address {
+    Address(address.0 - address.0 % BYTES_PER_LINE)
+}
+
+fn parse_hex_bytes(input: &str) -> Result<Vec<u8>, String> {
+    let digits = input
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<Vec<char>>();
+    if digits.is_empty() || digits.len() % 2 != 0 {
+        return Err(format!("'{}' is not a sequence of hex bytes", input));
+    }
+    digits
+        .chunks(2)
+        .map(|pair| {
+            let pair = pair.iter().collect::<String>();
+            u8::from_str_radix(&pair, 16).map_err(|_| format!("'{}' is not a hex byte", pair))
+        })
+        .collect()
+}
+
+struct HexDump {
+    begin: Option<Address>,
+    bytes: Vec<Option<u8>>,
+    selected: Address,
+    // Contents at the previous stop
+    previous: HashMap<Address, u8>,
+}
+
+impl HexDump {
+    fn end(&self) -> Option<Address> {
+        self.begin.map(|b| b + self.bytes.len())
+    }
+
+    fn byte_style(&self, address: Address, byte: Option<u8>) -> StyleModifier {
+        match (self.previous.get(&address), byte) {
+            (Some(old), Some(new)) if *old != new => {
+                StyleModifier::new().fg_color(Color::Red).bold(true)
+            }
+            _ => StyleModifier::new(),
+        }
+    }
+}
+
+impl Widget for HexDump {
+    fn space_demand(&self) -> Demand2D {
+        Demand2D {
+            // address, hex bytes and ascii column
+            width: Demand::at_least(20 + BYTES_PER_LINE * 4 + 3),
+            height: Demand::at_least(1),
+        }
+    }
+    fn draw(&self, mut window: Window, hints: RenderingHints) {
+        use std::fmt::Write;
+        let begin = if let Some(begin) = self.begin {
+            begin
+        } else {
+            let mut c = Cursor::new(&mut window);
+            let _ = write!(c, "No address selected");
+            return;
+        };
+        let visible_lines = window.get_height().raw_value() as usize;
+        let selected_line = (self.selected.0 - begin.0) / BYTES_PER_LINE;
+        let first_line = if visible_lines > 0 && selected_line >= visible_lines {
+            selected_line + 1 - visible_lines
+        } else {
+            0
+        };
+
+        let mut c = Cursor::new(&mut window);
+        for (line_number, line) in self
+            .bytes
+            .chunks(BYTES_PER_LINE)
+            .enumerate()
+            .skip(first_line)
+        {
+            let line_address = begin + line_number * BYTES_PER_LINE;
+            let _ = write!(c, "0x{:016x}  ", line_address.0);
+            for (i, byte) in line.iter().enumerate() {
+                let address = line_address + i;
+                {
+                    let mut c = c.save().style_modifier();
+                    let style = self.byte_style(address, *byte);
+                    c.set_style_modifier(if address == self.selected {
+                        style.invert(hints.active)
+                    } else {
+                        style
+                    });
+                    match byte {
+                        Some(b) => {
+                            let _ = write!(c, "{:02x}", b);
+                        }
+                        None => {
+                            let _ = write!(c, "??");
+                        }
+                    }
+                }
+                let _ = write!(c, " ");
+                if i + 1 == BYTES_PER_LINE / 2 {
+                    let _ = write!(c, " ");
+                }
+            }
+            let _ = write!(c, " |");
+            for (i, byte) in line.iter().enumerate() {
+                let mut c = c.save().style_modifier();
+                c.set_style_modifier(self.byte_style(line_address + i, *byte));
+                let character = match byte {
+                    Some(b) if b.is_ascii_graphic() || *b == b' ' => *b as char,
+                    Some(_) => '.',
+                    None => '?',
+                };
+                let _ = write!(c, "{}", character);
+            }
+            let _ = write!(c, "|");
+            c.wrap_line();
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum PromptKind {
+    Address,
+    Write,
+}
+
+static IDLE_PROMPT: &'static str = "";
+
+pub struct MemoryView<'a> {
+    keymap: &'a Keymap,
+    dump: HexDump,
+    prompt_line: PromptLine,
+    prompt: Option<PromptKind>,
+    layout: VerticalLayout,
+}
+
+impl<'a> MemoryView<'a> {
+    pub fn new(keymap: &'a Keymap) -> Self {
+        MemoryView {
+            keymap: keymap,
+            dump: HexDump {
+                begin: None,
+                bytes: Vec::new(),
+                selected: Address(0),
+                previous: HashMap::new(),
+            },
+            prompt_line: PromptLine::with_prompt(IDLE_PROMPT.into()),
+            prompt: None,
+            layout: VerticalLayout::new(SeparatingStyle::Draw(
+                GraphemeCluster::try_from('=').unwrap(),
+            )),
+        }
+    }
+
+    fn reload(&mut self, p: ::UpdateParameters) {
+        if let Some(begin) = self.dump.begin {
+            match p.gdb.read_memory(begin, LOADED_BYTES) {
+                Ok(bytes) => self.dump.bytes = bytes,
+                Err(e) => p
+                    .message_sink
+                    .send(format!("Cannot read memory at {}: {:?}", begin, e)),
+            }
+        }
+    }
+
+    pub fn update_after_stop(&mut self, p: ::UpdateParameters) {
+        if let Some(begin) = self.dump.begin {
+            self.dump.previous = self
+                .dump
+                .bytes
+                .iter()
+                .enumerate()
+                .filter_map(|(i, byte)| byte.map(|b| (begin + i, b)))
+                .collect();
+            self.reload(p);
+        }
+    }
+
+    // Reload the dump if memory was changed by gdb (e.g., by assigning a variable in the console).
+    pub fn handle_memory_change(&mut self, address: Address, length: usize, p: ::UpdateParameters) {
+        if let (Some(begin), Some(end)) = (self.dump.begin, self.dump.end()) {
+            if address < end && begin < address + length {
+                self.reload(p);
+            }
+        }
+    }
+
+    fn show_address(&mut self, address: Address, p: ::UpdateParameters) {
+        self.dump.selected = address;
+        self.dump.begin = Some(line_begin(address));
+        self.dump.previous.clear();
+        self.reload(p);
+    }
+
+    fn move_selection(&mut self, forwards: bool, distance: usize, p: ::UpdateParameters) {
+        let (begin, end) = match (self.dump.begin, self.dump.end()) {
+            (Some(begin), Some(end)) => (begin, end),
+            _ => return,
+        };
+        let selected = if forwards {
+            Address(self.dump.selected.0.saturating_add(distance))
+        } else {
+            Address(self.dump.selected.0.saturating_sub(distance))
+        };
+        self.dump.selected = selected;
+        if selected < begin {
+            self.dump.begin = Some(line_begin(selected));
+            self.reload(p);
+        } else if selected >= end {
+            let last_line = line_begin(selected);
+            self.dump.begin = Some(Address(
+                last_line.0.saturating_sub((LINES - 1) * BYTES_PER_LINE),
+            ));
+            self.reload(p);
+        }
+    }
+
+    fn start_prompt(&mut self, kind: PromptKind) {
+        let prompt = match kind {
+            PromptKind::Address => "Address expression: ".to_owned(),
+            PromptKind::Write => {
+                if self.dump.begin.is_none() {
+                    return;
+                }
+                format!("Hex bytes to write at {}: ", self.dump.selected)
+            }
+        };
+        self.prompt_line.set_prompt(prompt);
+        self.prompt = Some(kind);
+    }
+
+    fn stop_prompt(&mut self) {
+        self.prompt = None;
+        self.prompt_line.finish_line();
+        self.prompt_line.set_prompt(IDLE_PROMPT.to_owned());
+    }
+
+    fn submit(&mut self, p: ::UpdateParameters) {
+        let line = self.prompt_line.active_line().trim().to_owned();
+        match self.prompt {
+            Some(PromptKind::Address) => match p.gdb.evaluate_address(&line) {
+                Ok(address) => {
+                    self.stop_prompt();
+                    self.show_address(address, p);
+                }
+                Err(e) => p
+                    .message_sink
+                    .send(format!("Cannot evaluate '{}': {:?}", line, e)),
+            },
+            Some(PromptKind::Write) => {
+                let bytes = match parse_hex_bytes(&line) {
+                    Ok(bytes) => bytes,
+                    Err(e) => {
+                        p.message_sink.send(e);
+                        return;
+                    }
+                };
+                match p.gdb.write_memory(self.dump.selected, &bytes) {
+                    Ok(()) => {
+                        self.stop_prompt();
+                        self.reload(p);
+                    }
+                    Err(e) => p.message_sink.send(format!(
+                        "Cannot write memory at {}: {:?}",
+                        self.dump.selected, e
+                    )),
+                }
+            }
+            None => {}
+        }
+    }
+}
+
+impl<'a> Widget for MemoryView<'a> {
+    fn space_demand(&self) -> Demand2D {
+        let widgets: Vec<&Widget> = vec![&self.dump, &self.prompt_line];
+        self.layout.space_demand(widgets.as_slice())
+    }
+    fn draw(&self, window: Window, hints: RenderingHints) {
+        self.layout.draw(
+            window,
+            &[
+                (&self.dump, hints.active(self.prompt.is_none())),
+                (&self.prompt_line, hints.active(self.prompt.is_some())),
+            ],
+        )
+    }
+}
+
+impl<'a> Container<::UpdateParametersStruct> for MemoryView<'a> {
+    fn input(&mut self, input: Input, p: ::UpdateParameters) -> Option<Input> {
+        let keymap = self.keymap;
+        if self.prompt.is_some() {
+            input
+                .chain(keymap.on(Action::PromptSubmit, || self.submit(p)))
+                .chain(keymap.on(Action::PromptCancel, || self.stop_prompt()))
+                .chain(
+                    EditBehavior::new(&mut self.prompt_line)
+                        .left_on(Key::Left)
+                        .right_on(Key::Right)
+                        .delete_forwards_on(Key::Delete)
+                        .delete_backwards_on(Key::Backspace)
+                        .go_to_beginning_of_line_on(Key::Home)
+                        .go_to_end_of_line_on(Key::End),
+                )
+                .finish()
+        } else {
+            input
+                .chain(keymap.on(Action::MemorySetAddress, || {
+                    self.start_prompt(PromptKind::Address)
+                }))
+                .chain(keymap.on(Action::MemoryWrite, || {
+                    self.start_prompt(PromptKind::Write)
+                }))
+                .chain(keymap.on(Action::MemoryLeft, || self.move_selection(false, 1, p)))
+                .chain(keymap.on(Action::MemoryRight, || self.move_selection(true, 1, p)))
+                .chain(keymap.on(Action::MemoryUp, || {
+                    self.move_selection(false, BYTES_PER_LINE, p)
+                }))
+                .chain(keymap.on(Action::MemoryDown, || {
+                    self.move_selection(true, BYTES_PER_LINE, p)
+                }))
+                .chain(keymap.on(Action::MemoryPageUp, || {
+                    self.move_selection(false, LOADED_BYTES, p)
+                }))
+                .chain(keymap.on(Action::MemoryPageDown, || {
+                    self.move_selection(true, LOADED_BYTES, p)
+                }))
+                .finish()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_hex_bytes() {
+        assert_eq!(parse_hex_bytes("deadbeef"), Ok(vec![0xde, 0xad, 0xbe, 0xef]));
+        assert_eq!(parse_hex_bytes("00 ff 1a"), Ok(vec![0x00, 0xff, 0x1a]));
+        assert!(parse_hex_bytes("").is_err());
+        assert!(parse_hex_bytes("abc").is_err());
+        assert!(parse_hex_bytes("zz").is_err());
+    }
+}
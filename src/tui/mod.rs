@@ -1,7 +1,19 @@
+pub mod attach;
+pub mod backtrace;
+pub mod breakpoints;
 pub mod commands;
 pub mod console;
+pub mod core_summary;
 pub mod expression_table;
+pub mod help;
+pub mod libraries;
+pub mod locals;
+pub mod memory;
+pub mod registers;
+pub mod select_list;
 pub mod srcview;
+pub mod status_bar;
+pub mod threads;
 pub mod tui;
 
 pub use self::tui::*;
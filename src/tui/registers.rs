@@ -0,0 +1,203 @@
+use gdb::Register;
+use gdbmi::commands::RegisterFormat;
+use keymap::{Action, Keymap};
+use log::debug;
+use std::collections::HashSet;
+use tui::select_list::{ListRow, SelectList};
+
+use unsegen::base::{Color, StyleModifier, Window};
+use unsegen::container::Container;
+use unsegen::input::Input;
+use unsegen::widget::{Demand2D, RenderingHints, Widget};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RegisterGroup {
+    GeneralPurpose,
+    Flags,
+    Vector,
+    Other,
+}
+
+impl RegisterGroup {
+    // Best-effort classification by name, covering the common names on x86 and arm.
+    pub fn of(register_name: &str) -> Self {
+        const FLAGS: &[&str] = &["eflags", "cpsr", "fpscr", "fpsr", "fpcr", "mxcsr"];
+        const GENERAL_PURPOSE: &[&str] = &[
+            "rax", "rbx", "rcx", "rdx", "rsi", "rdi", "rbp", "rsp", "rip", "eax", "ebx", "ecx",
+            "edx", "esi", "edi", "ebp", "esp", "eip", "pc", "sp", "lr", "fp",
+        ];
+        const GENERAL_PURPOSE_PREFIXES: &[&str] = &["r", "x", "w"];
+        const VECTOR_PREFIXES: &[&str] = &["xmm", "ymm", "zmm", "k", "v", "q", "d", "s"];
+
+        let name = register_name.to_lowercase();
+        // e.g., "xmm0" for prefix "xmm"
+        let numbered = |prefix: &&str| {
+            name.len() > prefix.len()
+                && name.starts_with(prefix)
+                && name[prefix.len()..].chars().all(|c| c.is_ascii_digit())
+        };
+        if FLAGS.contains(&name.as_str()) {
+            RegisterGroup::Flags
+        } else if GENERAL_PURPOSE.contains(&name.as_str())
+            || GENERAL_PURPOSE_PREFIXES.iter().any(&numbered)
+        {
+            RegisterGroup::GeneralPurpose
+        } else if VECTOR_PREFIXES.iter().any(&numbered) {
+            RegisterGroup::Vector
+        } else {
+            RegisterGroup::Other
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            RegisterGroup::GeneralPurpose => "General purpose",
+            RegisterGroup::Flags => "Flags",
+            RegisterGroup::Vector => "Vector",
+            RegisterGroup::Other => "Other",
+        }
+    }
+}
+
+pub struct RegisterList<'a> {
+    keymap: &'a Keymap,
+    list: SelectList,
+    names: Vec<String>,
+    format: RegisterFormat,
+    registers: Vec<Register>,
+    changed: HashSet<usize>,
+}
+
+impl<'a> RegisterList<'a> {
+    pub fn new(keymap: &'a Keymap) -> Self {
+        RegisterList {
+            keymap: keymap,
+            list: SelectList::new(vec!["Register", "Value"]),
+            names: Vec::new(),
+            format: RegisterFormat::Hexadecimal,
+            registers: Vec::new(),
+            changed: HashSet::new(),
+        }
+    }
+
+    fn update_rows(&mut self) {
+        let mut rows = Vec::new();
+        for group in &[
+            RegisterGroup::GeneralPurpose,
+            RegisterGroup::Flags,
+            RegisterGroup::Vector,
+            RegisterGroup::Other,
+        ] {
+            let mut registers = self
+                .registers
+                .iter()
+                .filter(|r| RegisterGroup::of(&r.name) == *group)
+                .peekable();
+            if registers.peek().is_none() {
+                continue;
+            }
+            rows.push(
+                ListRow::new(vec![format!("[{}]", group.description()), String::new()])
+                    .style(StyleModifier::new().bold(true)),
+            );
+            for register in registers {
+                let style = if self.changed.contains(&register.number) {
+                    StyleModifier::new().fg_color(Color::Red).bold(true)
+                } else {
+                    StyleModifier::new()
+                };
+                rows.push(
+                    ListRow::new(vec![register.name.clone(), register.value.clone()]).style(style),
+                );
+            }
+        }
+        self.list.set_rows(rows);
+    }
+
+    fn reload_values(&mut self, p: ::UpdateParameters) {
+        if self.names.is_empty() {
+            match p.gdb.get_register_names() {
+                Ok(names) => self.names = names,
+                Err(e) => debug!("Failed to get register names: {:?}", e),
+            }
+        }
+        self.registers = match p.gdb.get_registers(&self.names, self.format) {
+            Ok(registers) => registers,
+            Err(e) => {
+                debug!("Failed to get register values: {:?}", e);
+                Vec::new()
+            }
+        };
+        self.update_rows();
+    }
+
+    // Reload all registers after the program stopped.
+    pub fn update_after_stop(&mut self, p: ::UpdateParameters) {
+        self.changed = match p.gdb.get_changed_registers() {
+            Ok(changed) => changed.into_iter().collect(),
+            Err(e) => {
+                debug!("Failed to get changed registers: {:?}", e);
+                HashSet::new()
+            }
+        };
+        self.reload_values(p);
+    }
+
+    // Reload the register values, e.g., after another frame was selected.
+    pub fn update(&mut self, p: ::UpdateParameters) {
+        self.reload_values(p);
+    }
+
+    fn toggle_format(&mut self, p: ::UpdateParameters) {
+        self.format = match self.format {
+            RegisterFormat::Hexadecimal => RegisterFormat::Natural,
+            _ => RegisterFormat::Hexadecimal,
+        };
+        self.reload_values(p);
+    }
+}
+
+impl<'a> Widget for RegisterList<'a> {
+    fn space_demand(&self) -> Demand2D {
+        self.list.space_demand()
+    }
+    fn draw(&self, window: Window, hints: RenderingHints) {
+        self.list.draw(window, hints)
+    }
+}
+
+impl<'a> Container<::UpdateParametersStruct> for RegisterList<'a> {
+    fn input(&mut self, input: Input, p: ::UpdateParameters) -> Option<Input> {
+        let keymap = self.keymap;
+        input
+            .chain(keymap.on(Action::ListUp, || self.list.select_previous()))
+            .chain(keymap.on(Action::ListDown, || self.list.select_next()))
+            .chain(keymap.on(Action::ListToBeginning, || self.list.select_first()))
+            .chain(keymap.on(Action::ListToEnd, || self.list.select_last()))
+            .chain(keymap.on(Action::RegistersToggleFormat, || {
+                self.toggle_format(p)
+            }))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_register_group() {
+        assert_eq!(RegisterGroup::of("rax"), RegisterGroup::GeneralPurpose);
+        assert_eq!(RegisterGroup::of("r12"), RegisterGroup::GeneralPurpose);
+        assert_eq!(RegisterGroup::of("x29"), RegisterGroup::GeneralPurpose);
+        assert_eq!(RegisterGroup::of("pc"), RegisterGroup::GeneralPurpose);
+        assert_eq!(RegisterGroup::of("eflags"), RegisterGroup::Flags);
+        assert_eq!(RegisterGroup::of("cpsr"), RegisterGroup::Flags);
+        assert_eq!(RegisterGroup::of("xmm15"), RegisterGroup::Vector);
+        assert_eq!(RegisterGroup::of("ymm0"), RegisterGroup::Vector);
+        assert_eq!(RegisterGroup::of("v31"), RegisterGroup::Vector);
+        assert_eq!(RegisterGroup::of("fs_base"), RegisterGroup::Other);
+        assert_eq!(RegisterGroup::of("st0"), RegisterGroup::Other);
+        assert_eq!(RegisterGroup::of("x"), RegisterGroup::Other);
+    }
+}
@@ -0,0 +1,152 @@
+use unsegen::base::{Cursor, StyleModifier, Window};
+use unsegen::widget::{text_width, Demand, Demand2D, RenderingHints, Widget};
+
+pub struct ListRow {
+    pub cells: Vec<String>,
+    pub style: StyleModifier,
+}
+
+impl ListRow {
+    pub fn new(cells: Vec<String>) -> Self {
+        ListRow {
+            cells: cells,
+            style: StyleModifier::new(),
+        }
+    }
+
+    pub fn style(mut self, style: StyleModifier) -> Self {
+        self.style = style;
+        self
+    }
+}
+
+// A read-only table of text cells with one selected row, used by the various list-like containers.
+pub struct SelectList {
+    header: Vec<&'static str>,
+    rows: Vec<ListRow>,
+    selected: usize,
+}
+
+impl SelectList {
+    pub fn new(header: Vec<&'static str>) -> Self {
+        SelectList {
+            header: header,
+            rows: Vec::new(),
+            selected: 0,
+        }
+    }
+
+    pub fn set_rows(&mut self, rows: Vec<ListRow>) {
+        self.rows = rows;
+        self.selected = self.selected.min(self.rows.len().checked_sub(1).unwrap_or(0));
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn selected(&self) -> Option<usize> {
+        if self.rows.is_empty() {
+            None
+        } else {
+            Some(self.selected)
+        }
+    }
+
+    pub fn select(&mut self, index: usize) {
+        self.selected = index.min(self.rows.len().checked_sub(1).unwrap_or(0));
+    }
+
+    pub fn select_next(&mut self) {
+        let next = self.selected + 1;
+        self.select(next);
+    }
+
+    pub fn select_previous(&mut self) {
+        self.selected = self.selected.checked_sub(1).unwrap_or(0);
+    }
+
+    pub fn select_first(&mut self) {
+        self.selected = 0;
+    }
+
+    pub fn select_last(&mut self) {
+        self.selected = self.rows.len().checked_sub(1).unwrap_or(0);
+    }
+
+    fn column_widths(&self) -> Vec<usize> {
+        let mut widths = self
+            .header
+            .iter()
+            .map(|h| text_width(h).raw_value() as usize)
+            .collect::<Vec<_>>();
+        for row in &self.rows {
+            for (i, cell) in row.cells.iter().enumerate() {
+                let w = text_width(cell).raw_value() as usize;
+                if i < widths.len() {
+                    widths[i] = widths[i].max(w);
+                } else {
+                    widths.push(w);
+                }
+            }
+        }
+        widths
+    }
+
+    fn write_cells<'c, 'w>(cursor: &mut Cursor<'c, 'w>, cells: &[String], widths: &[usize])
+    where
+        'w: 'c,
+    {
+        use std::fmt::Write;
+        for (i, cell) in cells.iter().enumerate() {
+            if i + 1 < cells.len() {
+                let _ = write!(cursor, "{:width$} ", cell, width = widths[i]);
+            } else {
+                let _ = write!(cursor, "{}", cell);
+            }
+        }
+    }
+}
+
+impl Widget for SelectList {
+    fn space_demand(&self) -> Demand2D {
+        let width = self.column_widths().iter().map(|w| w + 1).sum::<usize>();
+        Demand2D {
+            width: Demand::at_least(width),
+            height: Demand::at_least(self.rows.len() + 1),
+        }
+    }
+    fn draw(&self, mut window: Window, hints: RenderingHints) {
+        let widths = self.column_widths();
+        let visible_rows = (window.get_height().raw_value() as usize)
+            .checked_sub(1)
+            .unwrap_or(0);
+        let first_row = if visible_rows > 0 && self.selected >= visible_rows {
+            self.selected + 1 - visible_rows
+        } else {
+            0
+        };
+
+        let mut cursor = Cursor::new(&mut window);
+        {
+            let mut cursor = cursor.save().style_modifier();
+            cursor.set_style_modifier(StyleModifier::new().bold(true));
+            let header = self.header.iter().map(|h| h.to_string()).collect::<Vec<_>>();
+            Self::write_cells(&mut cursor, &header, &widths);
+        }
+        cursor.wrap_line();
+        for (i, row) in self.rows.iter().enumerate().skip(first_row) {
+            {
+                let mut cursor = cursor.save().style_modifier();
+                let style = if i == self.selected {
+                    row.style.invert(hints.active)
+                } else {
+                    row.style
+                };
+                cursor.set_style_modifier(style);
+                Self::write_cells(&mut cursor, &row.cells, &widths);
+            }
+            cursor.wrap_line();
+        }
+    }
+}
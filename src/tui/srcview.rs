@@ -1,9 +1,12 @@
-use gdb::{response::*, Address, BreakPoint, BreakpointOperationError, SrcPosition};
+use gdb::{response::*, Address, BreakPoint, ExecutionDirection, InferiorState, SrcPosition};
 use gdbmi::commands::{BreakPointLocation, BreakPointNumber, DisassembleMode, MiCommand};
 use gdbmi::output::{JsonValue, Object, ResultClass};
 use gdbmi::ExecuteError;
+use keymap::{format_key, Action, Keymap};
+use tui::TuiEvent;
 use log::warn;
 use std::collections::HashSet;
+use std::fmt;
 use std::fs;
 use std::io;
 use std::ops::Range;
@@ -11,7 +14,7 @@ use std::path::{Path, PathBuf};
 use unsegen::base::basic_types::*;
 use unsegen::base::{Color, Cursor, GraphemeCluster, StyleModifier, Window};
 use unsegen::container::Container;
-use unsegen::input::{Input, Key, ScrollBehavior};
+use unsegen::input::{Input, Scrollable};
 use unsegen::widget::{
     text_width, ColDemand, Demand, Demand2D, HorizontalLayout, RenderingHints, SeparatingStyle,
     Widget,
@@ -87,7 +90,7 @@ impl AssemblyDecorator {
         let addresses = breakpoints
             .filter_map(|bp| {
                 bp.address.and_then(|addr| {
-                    if bp.enabled && address_range.start <= addr && addr < address_range.end {
+                    if address_range.start <= addr && addr < address_range.end {
                         Some(addr)
                     } else {
                         None
@@ -251,7 +254,7 @@ impl<'a> AssemblyView<'a> {
                 content.set_decorator(AssemblyDecorator::new(
                     min_address..max_address,
                     self.last_stop_position,
-                    p.gdb.breakpoints.values(),
+                    p.gdb.breakpoints.active_locations(),
                 ));
             }
         }
@@ -275,7 +278,7 @@ impl<'a> AssemblyView<'a> {
                 .with_decorator(AssemblyDecorator::new(
                     min_address..max_address,
                     self.last_stop_position,
-                    p.gdb.breakpoints.values(),
+                    p.gdb.breakpoints.active_locations(),
                 )),
         );
     }
@@ -383,42 +386,39 @@ impl<'a> AssemblyView<'a> {
                     .insert_breakpoint(BreakPointLocation::Address(line.address.0))
                 {
                     Ok(()) => {}
-                    Err(BreakpointOperationError::Busy) => {
+                    Err(e) => {
                         p.message_sink
-                            .send("Cannot insert breakpoint: Gdb is busy.");
-                    }
-                    Err(BreakpointOperationError::ExecutionError(msg)) => {
-                        p.message_sink
-                            .send(format!("Cannot insert breakpoint: {}", msg));
+                            .send(format!("Cannot insert breakpoint: {}", e));
                     }
                 }
             } else {
                 match p.gdb.delete_breakpoints(active_bps.into_iter()) {
                     Ok(()) => {}
-                    Err(BreakpointOperationError::Busy) => {
-                        p.message_sink
-                            .send("Cannot remove breakpoint: Gdb is busy.");
-                    }
-                    Err(BreakpointOperationError::ExecutionError(msg)) => {
+                    Err(e) => {
                         p.message_sink
-                            .send(format!("Cannot remove breakpoint: {}", msg));
+                            .send(format!("Cannot remove breakpoint: {}", e));
                     }
                 }
             }
         }
     }
-    fn event(&mut self, event: Input, p: ::UpdateParameters) -> Option<Input> {
+    fn event(&mut self, event: Input, keymap: &Keymap, p: ::UpdateParameters) -> Option<Input> {
         event
-            .chain(
-                ScrollBehavior::new(&mut self.pager)
-                    .forwards_on(Key::Down)
-                    .forwards_on(Key::Char('j'))
-                    .backwards_on(Key::Up)
-                    .backwards_on(Key::Char('k'))
-                    .to_beginning_on(Key::Home)
-                    .to_end_on(Key::End),
-            )
-            .chain((Key::Char(' '), || self.toggle_breakpoint(p)))
+            .chain(keymap.on(Action::SrcViewScrollDown, || {
+                let _ = self.pager.scroll_forwards();
+            }))
+            .chain(keymap.on(Action::SrcViewScrollUp, || {
+                let _ = self.pager.scroll_backwards();
+            }))
+            .chain(keymap.on(Action::SrcViewToBeginning, || {
+                let _ = self.pager.scroll_to_beginning();
+            }))
+            .chain(keymap.on(Action::SrcViewToEnd, || {
+                let _ = self.pager.scroll_to_end();
+            }))
+            .chain(keymap.on(Action::SrcViewToggleBreakpoint, || {
+                self.toggle_breakpoint(p)
+            }))
             .finish()
     }
 }
@@ -446,7 +446,7 @@ impl SourceDecorator {
         let addresses = breakpoints
             .filter_map(|bp| {
                 bp.src_pos.clone().and_then(|pos| {
-                    if bp.enabled && pos.file == file {
+                    if pos.file == file {
                         Some(pos.line)
                     } else {
                         None
@@ -598,7 +598,7 @@ impl<'a> SourceView<'a> {
             content.set_decorator(SourceDecorator::new(
                 file_path,
                 last_line_number,
-                p.gdb.breakpoints.values(),
+                p.gdb.breakpoints.active_locations(),
             ));
         }
     }
@@ -625,7 +625,7 @@ impl<'a> SourceView<'a> {
     ) -> Result<(), PagerShowError> {
         if self.need_to_load_file(path.as_ref()) {
             let path_ref = path.as_ref();
-            self.load(path_ref, p.gdb.breakpoints.values())
+            self.load(path_ref, p.gdb.breakpoints.active_locations())
                 .map_err(|e| PagerShowError::CouldNotOpenFile(path_ref.to_path_buf(), e))?;
         } else {
             let last_line_number = self.get_last_line_number_for(path.as_ref());
@@ -633,7 +633,7 @@ impl<'a> SourceView<'a> {
                 content.set_decorator(SourceDecorator::new(
                     path.as_ref(),
                     last_line_number,
-                    p.gdb.breakpoints.values(),
+                    p.gdb.breakpoints.active_locations(),
                 ));
             }
         }
@@ -700,34 +700,39 @@ impl<'a> SourceView<'a> {
                 })
                 .collect();
             if active_bps.is_empty() {
-                if p.gdb
+                if let Err(e) = p
+                    .gdb
                     .insert_breakpoint(BreakPointLocation::Line(path, line.into()))
-                    .is_err()
                 {
                     p.message_sink
-                        .send("Cannot insert breakpoint: Gdb is busy.");
+                        .send(format!("Cannot insert breakpoint: {}", e));
                 }
             } else {
-                if p.gdb.delete_breakpoints(active_bps.into_iter()).is_err() {
+                if let Err(e) = p.gdb.delete_breakpoints(active_bps.into_iter()) {
                     p.message_sink
-                        .send("Cannot remove breakpoint: Gdb is busy.");
+                        .send(format!("Cannot remove breakpoint: {}", e));
                 }
             }
         }
     }
 
-    fn event(&mut self, event: Input, p: ::UpdateParameters) -> Option<Input> {
+    fn event(&mut self, event: Input, keymap: &Keymap, p: ::UpdateParameters) -> Option<Input> {
         event
-            .chain(
-                ScrollBehavior::new(&mut self.pager)
-                    .forwards_on(Key::Down)
-                    .forwards_on(Key::Char('j'))
-                    .backwards_on(Key::Up)
-                    .backwards_on(Key::Char('k'))
-                    .to_beginning_on(Key::Home)
-                    .to_end_on(Key::End),
-            )
-            .chain((Key::Char(' '), || self.toggle_breakpoint(p)))
+            .chain(keymap.on(Action::SrcViewScrollDown, || {
+                let _ = self.pager.scroll_forwards();
+            }))
+            .chain(keymap.on(Action::SrcViewScrollUp, || {
+                let _ = self.pager.scroll_backwards();
+            }))
+            .chain(keymap.on(Action::SrcViewToBeginning, || {
+                let _ = self.pager.scroll_to_beginning();
+            }))
+            .chain(keymap.on(Action::SrcViewToEnd, || {
+                let _ = self.pager.scroll_to_end();
+            }))
+            .chain(keymap.on(Action::SrcViewToggleBreakpoint, || {
+                self.toggle_breakpoint(p)
+            }))
             .finish()
     }
 }
@@ -774,8 +779,6 @@ struct StackInfo {
 impl Widget for StackInfo {
     fn space_demand(&self) -> Demand2D {
         Demand2D {
-            // TODO: reenable this once configurable layouts are a thing.
-            /*
             width: Demand::at_least(
                 Width::new(
                     (self
@@ -788,8 +791,6 @@ impl Widget for StackInfo {
                 )
                 .unwrap(),
             ),
-            */
-            width: Demand::at_least(Width::new(1).unwrap()),
             height: Demand::exact(Height::new(1).unwrap()),
         }
     }
@@ -896,7 +897,57 @@ fn disassemble_address(
     }
 }
 
+// Line or instruction under the cursor of the source or assembly view.
+#[derive(Clone, PartialEq)]
+enum CursorLocation {
+    Line(PathBuf, LineNumber),
+    Address(Address),
+}
+
+impl CursorLocation {
+    fn breakpoint_location(&self) -> BreakPointLocation {
+        match self {
+            CursorLocation::Line(path, line) => BreakPointLocation::Line(path, (*line).into()),
+            CursorLocation::Address(address) => BreakPointLocation::Address(address.0),
+        }
+    }
+
+    // Start address of the function containing the location (if gdb knows the function).
+    fn function_start(&self, p: ::UpdateParameters) -> Option<Address> {
+        let command = match self {
+            CursorLocation::Line(path, line) => MiCommand::data_disassemble_file(
+                path,
+                (*line).into(),
+                Some(1),
+                DisassembleMode::DisassemblyOnly,
+            ),
+            CursorLocation::Address(address) => MiCommand::data_disassemble_address(
+                address.0,
+                address.0 + 1,
+                DisassembleMode::DisassemblyOnly,
+            ),
+        };
+        let result = p.gdb.mi.execute(command).ok()?;
+        let instruction = result.results["asm_insns"].members().next()?;
+        let address = get_addr(instruction, "address").ok()?;
+        let debug_location = AssemblyDebugLocation::try_from_value(instruction)?;
+        Some(address - debug_location.offset)
+    }
+}
+
+impl fmt::Display for CursorLocation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CursorLocation::Line(path, line) => {
+                write!(f, "Line {} of {}", line, path.to_string_lossy())
+            }
+            CursorLocation::Address(address) => write!(f, "Address {}", address),
+        }
+    }
+}
+
 pub struct CodeWindow<'a> {
+    keymap: &'a Keymap,
     src_view: SourceView<'a>,
     asm_view: AssemblyView<'a>,
     layout: HorizontalLayout,
@@ -905,11 +956,18 @@ pub struct CodeWindow<'a> {
     asm_state: AsmContentState,
     last_bp_update: ::std::time::Instant,
     stack_info: StackInfo,
+    // Jump target outside of the current function that has to be confirmed by jumping again
+    pending_jump: Option<CursorLocation>,
 }
 
 impl<'a> CodeWindow<'a> {
-    pub fn new(highlighting_theme: &'a Theme, welcome_msg: &'static str) -> Self {
+    pub fn new(
+        highlighting_theme: &'a Theme,
+        keymap: &'a Keymap,
+        welcome_msg: &'static str,
+    ) -> Self {
         CodeWindow {
+            keymap: keymap,
             src_view: SourceView::new(highlighting_theme),
             asm_view: AssemblyView::new(highlighting_theme),
             layout: HorizontalLayout::new(SeparatingStyle::Draw(
@@ -920,6 +978,7 @@ impl<'a> CodeWindow<'a> {
             asm_state: AsmContentState::Unavailable,
             last_bp_update: ::std::time::Instant::now(),
             stack_info: Default::default(),
+            pending_jump: None,
         }
     }
 
@@ -1142,6 +1201,68 @@ impl<'a> CodeWindow<'a> {
         self.src_view.update_decoration(p);
     }
 
+    // Show the assembly next to the source code (or only the assembly if the source is not
+    // available), e.g., to see the faulting instruction of a core dump.
+    pub fn prefer_assembly(&mut self) {
+        self.preferred_mode = DisplayMode::SideBySide;
+    }
+
+    // Show an arbitrary location (e.g., of a breakpoint) without changing the last stop position.
+    pub fn show_location(
+        &mut self,
+        src_pos: Option<&SrcPosition>,
+        address: Option<Address>,
+        p: ::UpdateParameters,
+    ) {
+        if let DisplayMode::Message(_) = self.preferred_mode {
+            self.preferred_mode = DisplayMode::Source;
+        }
+
+        if let Some(pos) = src_pos {
+            self.src_state = match self.src_view.current_file() {
+                Some(f) if f == pos.file => SrcContentState::Available,
+                _ => SrcContentState::NotYetLoaded(pos.file.clone()),
+            };
+            self.asm_state = if self
+                .asm_view
+                .go_to_first_applicable_line(&pos.file, pos.line)
+                .is_ok()
+            {
+                AsmContentState::Available
+            } else {
+                AsmContentState::NotYetLoadedFile(pos.file.clone(), pos.line.into())
+            };
+        } else if let Some(address) = address {
+            self.src_state = SrcContentState::Unavailable;
+            if self.asm_view.go_to_address(address).is_ok() {
+                self.asm_state = AsmContentState::Available;
+            } else {
+                match Self::find_function_range(address, p)
+                    .or_else(|_| Self::find_valid_address_range(address, 128, p))
+                {
+                    Ok((begin, end)) => {
+                        self.asm_state = AsmContentState::NotYetLoadedAddr(begin, end)
+                    }
+                    Err(e) => warn!("Failed to disassemble from address {}: {:?}", address, e),
+                };
+            }
+        } else {
+            return;
+        }
+
+        self.try_load_active_content(p);
+        if let Some(pos) = src_pos {
+            let _ = self.src_view.go_to_line(pos.line);
+            let _ = self
+                .asm_view
+                .go_to_first_applicable_line(&pos.file, pos.line);
+        } else if let Some(address) = address {
+            let _ = self.asm_view.go_to_address(address);
+        }
+        self.asm_view.update_decoration(p);
+        self.src_view.update_decoration(p);
+    }
+
     fn toggle_mode(&mut self, p: ::UpdateParameters) {
         let mut sync_asm_to_src = false;
         let prev_mode = self.preferred_mode.clone();
@@ -1195,27 +1316,10 @@ impl<'a> CodeWindow<'a> {
         };
 
         if level != new_level {
-            p.gdb.mi.execute_later(MiCommand::select_frame(new_level));
-
-            match p.gdb.mi.execute(MiCommand::stack_info_frame(None)) {
-                Ok(o) => {
-                    if o.class == ResultClass::Done {
-                        if let JsonValue::Object(ref frame) = o.results["frame"] {
-                            self.show_frame(frame, p);
-                        } else {
-                            return Err(GDBResponseError::MissingField(
-                                "frame",
-                                JsonValue::Object(o.results.clone()),
-                            ));
-                        }
-                    } else {
-                        return Err(GDBResponseError::Other(format!(
-                            "Unexpected result class: {:?}",
-                            o.class
-                        )));
-                    }
-                }
-                Err(_) => return Ok(()), //Ignore
+            match p.gdb.select_frame(new_level) {
+                Ok(frame) => p.tui_events.send(TuiEvent::FrameSelected(frame)),
+                Err(GDBResponseError::Execution(_)) => return Ok(()), //Ignore
+                Err(e) => return Err(e),
             };
         }
         Ok(())
@@ -1229,6 +1333,138 @@ impl<'a> CodeWindow<'a> {
         }
     }
 
+    fn execute_movement(
+        &mut self,
+        command: MiCommand,
+        direction: ExecutionDirection,
+        p: ::UpdateParameters,
+    ) {
+        match p.gdb.execute_movement(command, direction) {
+            Ok(()) => {}
+            Err(GDBResponseError::Execution(ExecuteError::Busy)) => {
+                p.message_sink.send(format!(
+                    "Cannot execute: The program is running. Interrupt it first ({} in the \
+                     console).",
+                    self.key_name(Action::ConsoleInterrupt)
+                ));
+            }
+            Err(e) => p.message_sink.send(format!("Cannot execute: {}", e)),
+        }
+    }
+
+    // Step by instruction instead of by source line if the assembly is shown.
+    fn step(&mut self, line: MiCommand, instruction: MiCommand, p: ::UpdateParameters) {
+        let command = match self.available_display_mode() {
+            DisplayMode::Assembly | DisplayMode::SideBySide => instruction,
+            DisplayMode::Source | DisplayMode::Message(_) => line,
+        };
+        self.execute_movement(command, ExecutionDirection::Forward, p);
+    }
+
+    fn run(&mut self, p: ::UpdateParameters) {
+        match p.gdb.inferior_state {
+            InferiorState::NotStarted | InferiorState::Exited(_) => {
+                self.execute_movement(MiCommand::exec_run(), ExecutionDirection::Forward, p)
+            }
+            InferiorState::Running | InferiorState::Stopped => p
+                .message_sink
+                .send("The program is already running. Use 'run' in the console to restart it."),
+        }
+    }
+
+    // The global.* stepping keys are handled regardless of the active container (see main.rs). They
+    // behave like the corresponding keys of the code window.
+    pub fn handle_global_stepping_keys(
+        &mut self,
+        input: Input,
+        p: ::UpdateParameters,
+    ) -> Option<Input> {
+        let keymap = self.keymap;
+        input
+            .chain(keymap.on(Action::GlobalNext, || {
+                self.step(MiCommand::exec_next(), MiCommand::exec_next_instruction(), p)
+            }))
+            .chain(keymap.on(Action::GlobalStep, || {
+                self.step(MiCommand::exec_step(), MiCommand::exec_step_instruction(), p)
+            }))
+            .chain(keymap.on(Action::GlobalFinish, || {
+                self.execute_movement(MiCommand::exec_finish(), ExecutionDirection::Forward, p)
+            }))
+            .chain(keymap.on(Action::GlobalContinue, || {
+                self.execute_movement(MiCommand::exec_continue(), ExecutionDirection::Forward, p)
+            }))
+            .chain(keymap.on(Action::GlobalRun, || self.run(p)))
+            .finish()
+    }
+
+    // Name of the (first) key bound to the action for messages.
+    fn key_name(&self, action: Action) -> String {
+        self.keymap
+            .keys(action)
+            .first()
+            .map(format_key)
+            .unwrap_or_default()
+    }
+
+    fn cursor_location(&self) -> Option<CursorLocation> {
+        match self.available_display_mode() {
+            DisplayMode::Assembly | DisplayMode::SideBySide => self
+                .asm_view
+                .pager
+                .current_line()
+                .map(|line| CursorLocation::Address(line.address)),
+            DisplayMode::Source => self.src_view.current_file().map(|file| {
+                CursorLocation::Line(file.to_owned(), self.src_view.current_line_number())
+            }),
+            DisplayMode::Message(_) => None,
+        }
+    }
+
+    fn run_to_cursor(&mut self, p: ::UpdateParameters) {
+        if let Some(location) = self.cursor_location() {
+            self.execute_movement(
+                MiCommand::exec_until(location.breakpoint_location()),
+                ExecutionDirection::Forward,
+                p,
+            );
+        }
+    }
+
+    // Jumping to another function is rarely intended (and usually corrupts the stack), so it has
+    // to be confirmed by jumping to the same location again.
+    fn jump_to_cursor(&mut self, confirmed: Option<CursorLocation>, p: ::UpdateParameters) {
+        let location = match self.cursor_location() {
+            Some(location) => location,
+            None => return,
+        };
+        if let Err(e) = p.gdb.check_live_program() {
+            p.message_sink.send(format!("Cannot jump: {}", e));
+            return;
+        }
+        if confirmed.as_ref() != Some(&location) {
+            let pc = p
+                .gdb
+                .last_stop
+                .as_ref()
+                .and_then(|stop| stop.frame.as_ref())
+                .and_then(|frame| get_addr_obj(frame, "addr").ok())
+                .or(self.asm_view.last_stop_position);
+            let current_function = pc.and_then(|pc| CursorLocation::Address(pc).function_start(p));
+            if current_function.is_none() || current_function != location.function_start(p) {
+                p.message_sink.send(format!(
+                    "{} is not in the current function. Press {} again to jump anyway.",
+                    location,
+                    self.key_name(Action::CodeWindowJumpToCursor)
+                ));
+                self.pending_jump = Some(location);
+                return;
+            }
+        }
+        if let Err(e) = p.gdb.jump(location.breakpoint_location()) {
+            p.message_sink.send(format!("Cannot jump: {}", e));
+        }
+    }
+
     pub fn update_after_event(&mut self, p: ::UpdateParameters) {
         if p.gdb.breakpoints.last_change > self.last_bp_update {
             self.asm_view.update_decoration(p);
@@ -1284,13 +1520,49 @@ impl<'a> Widget for CodeWindow<'a> {
 
 impl<'a> Container<::UpdateParametersStruct> for CodeWindow<'a> {
     fn input(&mut self, input: Input, p: ::UpdateParameters) -> Option<Input> {
+        let keymap = self.keymap;
+        // A pending jump is only confirmed by the next input.
+        let pending_jump = self.pending_jump.take();
         input
-            .chain((Key::Char('d'), || self.toggle_mode(p)))
-            .chain((Key::PageUp, || self.switch_stackframe(p, true)))
-            .chain((Key::PageDown, || self.switch_stackframe(p, false)))
+            .chain(keymap.on(Action::CodeWindowToggleMode, || self.toggle_mode(p)))
+            .chain(keymap.on(Action::CodeWindowStackUp, || {
+                self.switch_stackframe(p, true)
+            }))
+            .chain(keymap.on(Action::CodeWindowStackDown, || {
+                self.switch_stackframe(p, false)
+            }))
+            .chain(keymap.on(Action::CodeWindowNext, || {
+                self.step(MiCommand::exec_next(), MiCommand::exec_next_instruction(), p)
+            }))
+            .chain(keymap.on(Action::CodeWindowStep, || {
+                self.step(MiCommand::exec_step(), MiCommand::exec_step_instruction(), p)
+            }))
+            .chain(keymap.on(Action::CodeWindowFinish, || {
+                self.execute_movement(MiCommand::exec_finish(), ExecutionDirection::Forward, p)
+            }))
+            .chain(keymap.on(Action::CodeWindowContinue, || {
+                self.execute_movement(MiCommand::exec_continue(), ExecutionDirection::Forward, p)
+            }))
+            .chain(keymap.on(Action::CodeWindowRun, || self.run(p)))
+            .chain(keymap.on(Action::CodeWindowReverseNext, || {
+                self.execute_movement(MiCommand::exec_next(), ExecutionDirection::Reverse, p)
+            }))
+            .chain(keymap.on(Action::CodeWindowReverseStep, || {
+                self.execute_movement(MiCommand::exec_step(), ExecutionDirection::Reverse, p)
+            }))
+            .chain(keymap.on(Action::CodeWindowReverseFinish, || {
+                self.execute_movement(MiCommand::exec_finish(), ExecutionDirection::Reverse, p)
+            }))
+            .chain(keymap.on(Action::CodeWindowReverseContinue, || {
+                self.execute_movement(MiCommand::exec_continue(), ExecutionDirection::Reverse, p)
+            }))
+            .chain(keymap.on(Action::CodeWindowRunToCursor, || self.run_to_cursor(p)))
+            .chain(keymap.on(Action::CodeWindowJumpToCursor, || {
+                self.jump_to_cursor(pending_jump, p)
+            }))
             .chain(|i: Input| match self.available_display_mode() {
                 DisplayMode::Assembly | DisplayMode::SideBySide => {
-                    let ret = self.asm_view.event(i, p);
+                    let ret = self.asm_view.event(i, keymap, p);
                     if let Some(src_pos) = self
                         .asm_view
                         .pager
@@ -1303,7 +1575,7 @@ impl<'a> Container<::UpdateParametersStruct> for CodeWindow<'a> {
                     }
                     ret
                 }
-                DisplayMode::Source => self.src_view.event(i, p),
+                DisplayMode::Source => self.src_view.event(i, keymap, p),
                 DisplayMode::Message(_) => Some(i),
             })
             .finish()
@@ -0,0 +1,102 @@
+use gdb::{ExecutionDirection, InferiorState, GDB};
+use gdbmi::output::Object;
+use std::path::Path;
+use unsegen::base::{Cursor, StyleModifier, Window};
+use unsegen::widget::{text_width, Demand, Demand2D, RenderingHints, Widget};
+
+// One line summary of the session that is shown below the containers.
+pub struct StatusBar<'a> {
+    input_mode: &'static str,
+    gdb: &'a GDB,
+    frame: Option<&'a Object>,
+    ipc_socket: &'a Path,
+    message: Option<&'a str>,
+}
+
+impl<'a> StatusBar<'a> {
+    pub fn new(
+        input_mode: &'static str,
+        gdb: &'a GDB,
+        frame: Option<&'a Object>,
+        ipc_socket: &'a Path,
+        message: Option<&'a str>,
+    ) -> Self {
+        StatusBar {
+            input_mode: input_mode,
+            gdb: gdb,
+            frame: frame,
+            ipc_socket: ipc_socket,
+            message: message,
+        }
+    }
+
+    fn segments(&self) -> Vec<String> {
+        let mut segments = vec![self.input_mode.to_owned()];
+
+        if let Some(ref status) = self.gdb.unexpected_exit {
+            segments.push(format!("gdb exited: {}", status));
+        } else if let Some(ref core) = self.gdb.core_dump {
+            segments.push(match core.signal_name() {
+                Some(signal) => format!("core dump: {}", signal),
+                None => "core dump".to_owned(),
+            });
+        } else {
+            let state = self.gdb.inferior_state;
+            let mut state_segment = match (state, &self.gdb.last_stop) {
+                (InferiorState::Stopped, &Some(ref stop)) => {
+                    format!("{}: {}", state, stop.summary())
+                }
+                (InferiorState::Exited(None), &Some(ref stop)) => stop.summary(),
+                _ => state.to_string(),
+            };
+            if let Some(pid) = self.gdb.inferior_pid {
+                state_segment.push_str(&format!(" (pid {})", pid));
+            }
+            if self.gdb.execution_direction == ExecutionDirection::Reverse {
+                state_segment.push_str(" ◀ reverse");
+            }
+            segments.push(state_segment);
+        }
+
+        if let Some(ref remote) = self.gdb.remote {
+            segments.push(remote.to_string());
+        }
+
+        if let (Some(thread), None) = (self.gdb.threads.current, &self.gdb.unexpected_exit) {
+            let mut location = format!("thread {}", thread);
+            if let Some(frame) = self.frame {
+                location.push_str(&format!(
+                    ", frame #{} {}",
+                    frame["level"].as_str().unwrap_or("0"),
+                    frame["func"].as_str().unwrap_or("??")
+                ));
+            }
+            segments.push(location);
+        }
+
+        segments.push(format!("ipc: {}", self.ipc_socket.to_string_lossy()));
+
+        if let Some(message) = self.message.and_then(|m| m.lines().next()) {
+            segments.push(message.to_owned());
+        }
+        segments
+    }
+}
+
+impl<'a> Widget for StatusBar<'a> {
+    fn space_demand(&self) -> Demand2D {
+        Demand2D {
+            width: Demand::at_least(1),
+            height: Demand::exact(1),
+        }
+    }
+    fn draw(&self, mut window: Window, _: RenderingHints) {
+        use std::fmt::Write;
+        let line = format!(" {}", self.segments().join(" │ "));
+        let padding = (window.get_width().raw_value() as usize)
+            .saturating_sub(text_width(&line).raw_value() as usize);
+        let mut c = Cursor::new(&mut window);
+        c.set_style_modifier(StyleModifier::new().invert(true));
+        let _ = write!(c, "{}{}", line, " ".repeat(padding));
+    }
+}
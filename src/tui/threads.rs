@@ -0,0 +1,153 @@
+use gdb::{Thread, ThreadState};
+use keymap::{Action, Keymap};
+use tui::select_list::{ListRow, SelectList};
+use tui::TuiEvent;
+
+use unsegen::base::{Color, StyleModifier, Window};
+use unsegen::container::Container;
+use unsegen::input::Input;
+use unsegen::widget::{Demand2D, RenderingHints, Widget};
+
+pub struct ThreadList<'a> {
+    keymap: &'a Keymap,
+    list: SelectList,
+    // Thread id of each row of the list
+    ids: Vec<u64>,
+    last_thread_update: ::std::time::Instant,
+}
+
+impl<'a> ThreadList<'a> {
+    pub fn new(keymap: &'a Keymap) -> Self {
+        ThreadList {
+            keymap: keymap,
+            list: SelectList::new(vec!["Id", "Name", "State", "Frame", "Core"]),
+            ids: Vec::new(),
+            last_thread_update: ::std::time::Instant::now(),
+        }
+    }
+
+    fn format_row(thread: &Thread, current: bool) -> ListRow {
+        let id = if current {
+            format!("* {}", thread.id)
+        } else {
+            format!("  {}", thread.id)
+        };
+        let name = thread
+            .name
+            .as_ref()
+            .or(thread.target_id.as_ref())
+            .cloned()
+            .unwrap_or_default();
+        let state = match thread.state {
+            ThreadState::Running => "running",
+            ThreadState::Stopped => "stopped",
+        };
+        let frame = if let Some(ref frame) = thread.frame {
+            let function = frame.function.clone().unwrap_or_else(|| "??".to_owned());
+            match (&frame.src_pos, frame.address) {
+                (&Some(ref pos), _) => format!(
+                    "{} at {}:{}",
+                    function,
+                    pos.file.to_string_lossy(),
+                    pos.line
+                ),
+                (&None, Some(address)) => format!("{} at {}", function, address),
+                (&None, None) => function,
+            }
+        } else {
+            String::new()
+        };
+        let style = match (current, thread.state) {
+            (true, _) => StyleModifier::new().bold(true),
+            (false, ThreadState::Running) => StyleModifier::new().fg_color(Color::LightBlack),
+            (false, ThreadState::Stopped) => StyleModifier::new(),
+        };
+        ListRow::new(vec![
+            id,
+            name,
+            state.to_owned(),
+            frame,
+            thread.core.map(|c| c.to_string()).unwrap_or_default(),
+        ])
+        .style(style)
+    }
+
+    fn update_rows(&mut self, p: ::UpdateParameters) {
+        let current = p.gdb.threads.current;
+        self.ids = p.gdb.threads.keys().cloned().collect();
+        self.list.set_rows(
+            p.gdb
+                .threads
+                .values()
+                .map(|t| Self::format_row(t, Some(t.id) == current))
+                .collect(),
+        );
+        if let Some(row) = current.and_then(|c| self.ids.iter().position(|&id| id == c)) {
+            self.list.select(row);
+        }
+    }
+
+    pub fn update_after_event(&mut self, p: ::UpdateParameters) {
+        if p.gdb.threads.last_change > self.last_thread_update {
+            self.update_rows(p);
+            self.last_thread_update = p.gdb.threads.last_change;
+        }
+    }
+
+    fn selected_id(&self) -> Option<u64> {
+        self.list.selected().and_then(|row| self.ids.get(row).cloned())
+    }
+
+    fn interrupt_thread(&mut self, p: ::UpdateParameters) {
+        if let Some(id) = self.selected_id() {
+            if let Err(e) = p.gdb.interrupt_thread(id) {
+                p.message_sink
+                    .send(format!("Cannot interrupt thread {}: {:?}", id, e));
+            }
+        }
+    }
+
+    fn continue_thread(&mut self, p: ::UpdateParameters) {
+        if let Some(id) = self.selected_id() {
+            if let Err(e) = p.gdb.continue_thread(id) {
+                p.message_sink
+                    .send(format!("Cannot continue thread {}: {:?}", id, e));
+            }
+        }
+    }
+
+    fn select_thread(&mut self, p: ::UpdateParameters) {
+        if let Some(id) = self.selected_id() {
+            match p.gdb.select_thread(id) {
+                Ok(frame) => p.tui_events.send(TuiEvent::ThreadSelected(frame)),
+                Err(e) => p
+                    .message_sink
+                    .send(format!("Cannot select thread {}: {:?}", id, e)),
+            }
+        }
+    }
+}
+
+impl<'a> Widget for ThreadList<'a> {
+    fn space_demand(&self) -> Demand2D {
+        self.list.space_demand()
+    }
+    fn draw(&self, window: Window, hints: RenderingHints) {
+        self.list.draw(window, hints)
+    }
+}
+
+impl<'a> Container<::UpdateParametersStruct> for ThreadList<'a> {
+    fn input(&mut self, input: Input, p: ::UpdateParameters) -> Option<Input> {
+        let keymap = self.keymap;
+        input
+            .chain(keymap.on(Action::ListUp, || self.list.select_previous()))
+            .chain(keymap.on(Action::ListDown, || self.list.select_next()))
+            .chain(keymap.on(Action::ListToBeginning, || self.list.select_first()))
+            .chain(keymap.on(Action::ListToEnd, || self.list.select_last()))
+            .chain(keymap.on(Action::ThreadsSelectThread, || self.select_thread(p)))
+            .chain(keymap.on(Action::ThreadsInterrupt, || self.interrupt_thread(p)))
+            .chain(keymap.on(Action::ThreadsContinue, || self.continue_thread(p)))
+            .finish()
+    }
+}
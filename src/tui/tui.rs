@@ -1,17 +1,59 @@
+use std::collections::HashSet;
+
 use unsegen_pager::Theme;
 
-use gdbmi::output::{AsyncClass, AsyncKind, JsonValue, Object, OutOfBandRecord, ThreadEvent};
+use gdbmi::output::{
+    AsyncClass, AsyncKind, JsonValue, Object, OutOfBandRecord, ResultRecord, StopEvent,
+    ThreadEvent,
+};
+use gdbmi::{AsyncResult, Token};
 
+use super::attach::AttachPicker;
+use super::backtrace::Backtrace;
+use super::breakpoints::BreakpointList;
 use super::console::Console;
+use super::core_summary::CoreSummary;
 use super::expression_table::ExpressionTable;
+use super::libraries::LibraryList;
+use super::locals::LocalsTable;
+use super::memory::MemoryView;
+use super::registers::RegisterList;
 use super::srcview::CodeWindow;
-use log::{debug, info};
+use super::threads::ThreadList;
+use gdb::{
+    is_remote_disconnect_message, watchpoint_stop_message, Address, SrcPosition, ThreadState,
+};
+use keymap::Keymap;
+use log::{debug, info, warn};
 use unsegen::container::{Container, ContainerProvider};
+use unsegen::input::Input;
 use unsegen_terminal::Terminal;
 
+// Requests of one container to another that are processed after the current event.
+pub enum TuiEvent {
+    ShowLocation(Option<SrcPosition>, Option<Address>),
+    FrameSelected(Object),
+    ThreadSelected(Object),
+    ShowAttachPicker,
+}
+
 pub struct Tui<'a> {
-    pub console: Console,
-    expression_table: ExpressionTable,
+    keymap: &'a Keymap,
+    // Containers that are part of the layout. Containers that are never shown are not updated.
+    visible_containers: HashSet<TuiContainerType>,
+    pub console: Console<'a>,
+    // Shown instead of the containers while the user chooses a process to attach to
+    pub attach_picker: Option<AttachPicker<'a>>,
+    selected_frame: Option<Object>,
+    expression_table: ExpressionTable<'a>,
+    breakpoints: BreakpointList<'a>,
+    backtrace: Backtrace<'a>,
+    threads: ThreadList<'a>,
+    locals: LocalsTable<'a>,
+    registers: RegisterList<'a>,
+    memory: MemoryView<'a>,
+    libraries: LibraryList<'a>,
+    core_summary: CoreSummary<'a>,
     process_pty: Terminal,
     src_view: CodeWindow<'a>,
 }
@@ -32,15 +74,36 @@ revision         "#,
 );
 
 impl<'a> Tui<'a> {
-    pub fn new(terminal: Terminal, highlighting_theme: &'a Theme) -> Self {
+    pub fn new(
+        terminal: Terminal,
+        highlighting_theme: &'a Theme,
+        keymap: &'a Keymap,
+        visible_containers: HashSet<TuiContainerType>,
+    ) -> Self {
         Tui {
-            console: Console::new(),
-            expression_table: ExpressionTable::new(),
+            keymap: keymap,
+            visible_containers: visible_containers,
+            console: Console::new(keymap),
+            attach_picker: None,
+            selected_frame: None,
+            expression_table: ExpressionTable::new(keymap),
+            breakpoints: BreakpointList::new(keymap),
+            backtrace: Backtrace::new(keymap),
+            threads: ThreadList::new(keymap),
+            locals: LocalsTable::new(keymap),
+            registers: RegisterList::new(keymap),
+            memory: MemoryView::new(keymap),
+            libraries: LibraryList::new(keymap),
+            core_summary: CoreSummary::new(keymap),
             process_pty: terminal,
-            src_view: CodeWindow::new(highlighting_theme, WELCOME_MSG),
+            src_view: CodeWindow::new(highlighting_theme, keymap, WELCOME_MSG),
         }
     }
 
+    fn shows(&self, container: TuiContainerType) -> bool {
+        self.visible_containers.contains(&container)
+    }
+
     fn handle_async_record(
         &mut self,
         kind: AsyncKind,
@@ -49,13 +112,59 @@ impl<'a> Tui<'a> {
         p: ::UpdateParameters,
     ) {
         match (kind, class) {
-            (AsyncKind::Exec, AsyncClass::Stopped)
-            | (AsyncKind::Notify, AsyncClass::Thread(ThreadEvent::Selected)) => {
+            (AsyncKind::Exec, AsyncClass::Stopped) => {
                 debug!("stopped: {}", JsonValue::Object(results.clone()).pretty(2));
-                if let JsonValue::Object(ref frame) = results["frame"] {
-                    self.src_view.show_frame(frame, p);
+                if p.gdb.is_own_interrupt_stop(results) {
+                    // The program was only interrupted in order to execute a command and is
+                    // already running again.
+                    return;
+                }
+                // Watchpoint stops are described in more detail (including the values)
+                let banner = watchpoint_stop_message(results)
+                    .unwrap_or_else(|| StopEvent::from_results(results).description());
+                p.message_sink.send(banner);
+                p.gdb.handle_stopped_event(results);
+                if p.gdb.mi.is_non_stop() && !self.follow_stopped_thread(results, p) {
+                    return;
+                }
+                if self.shows(TuiContainerType::Registers) {
+                    self.registers.update_after_stop(p);
+                }
+                if self.shows(TuiContainerType::Memory) {
+                    self.memory.update_after_stop(p);
+                }
+                self.show_stop_record(results, p);
+            }
+            (AsyncKind::Notify, AsyncClass::Thread(ThreadEvent::Selected)) => {
+                debug!(
+                    "thread selected: {}",
+                    JsonValue::Object(results.clone()).pretty(2)
+                );
+                if self.shows(TuiContainerType::Registers) {
+                    self.registers.update(p);
+                }
+                self.show_stop_record(results, p);
+            }
+            (AsyncKind::Exec, AsyncClass::Running) => {
+                p.gdb.handle_running_event(&results);
+            }
+            (AsyncKind::Notify, AsyncClass::Thread(event)) => {
+                p.gdb.handle_thread_event(event, &results);
+            }
+            (AsyncKind::Notify, AsyncClass::Library(event)) => {
+                p.gdb.handle_library_event(event, &results);
+            }
+            (AsyncKind::Notify, AsyncClass::MemoryChanged) => {
+                let address = results["addr"]
+                    .as_str()
+                    .and_then(|addr| Address::parse(addr).ok());
+                let length = results["len"]
+                    .as_str()
+                    .and_then(|len| usize::from_str_radix(len.trim_start_matches("0x"), 16).ok());
+                let shows_memory = self.shows(TuiContainerType::Memory);
+                if let (Some(address), Some(length), true) = (address, length, shows_memory) {
+                    self.memory.handle_memory_change(address, length, p);
                 }
-                self.expression_table.update_results(p);
             }
             (AsyncKind::Notify, AsyncClass::BreakPoint(event)) => {
                 debug!(
@@ -63,7 +172,10 @@ impl<'a> Tui<'a> {
                     event,
                     JsonValue::Object(results.clone()).pretty(2)
                 );
-                p.gdb.handle_breakpoint_event(event, &results);
+                if let Err(e) = p.gdb.handle_breakpoint_event(event, &results) {
+                    p.message_sink
+                        .send(format!("Cannot process breakpoint notification: {}", e));
+                }
             }
             (kind, class) => {
                 info!(
@@ -76,9 +188,121 @@ impl<'a> Tui<'a> {
         }
     }
 
+    // In non-stop mode, other threads may stop while the user inspects a stopped thread. We only
+    // switch to the stopped thread if the current thread is running (or there is none).
+    // Returns whether the stop should be shown.
+    fn follow_stopped_thread(&mut self, results: &Object, p: ::UpdateParameters) -> bool {
+        let stopped_thread = if let Some(id) = results["thread-id"]
+            .as_str()
+            .and_then(|id| id.parse::<u64>().ok())
+        {
+            id
+        } else {
+            return true;
+        };
+        let current = p.gdb.threads.current;
+        let current_is_stopped = current
+            .and_then(|id| p.gdb.threads.get(&id))
+            .map(|t| t.state == ThreadState::Stopped)
+            .unwrap_or(false);
+        if current == Some(stopped_thread) {
+            true
+        } else if current_is_stopped {
+            p.message_sink.send(format!(
+                "Thread {} stopped: {}",
+                stopped_thread,
+                results["reason"].as_str().unwrap_or("unknown reason")
+            ));
+            if let Err(e) = p.gdb.refresh_threads() {
+                warn!("Failed to update threads: {:?}", e);
+            }
+            false
+        } else {
+            if let Err(e) = p.gdb.select_thread(stopped_thread) {
+                warn!("Failed to select thread {}: {:?}", stopped_thread, e);
+            }
+            true
+        }
+    }
+
+    fn show_stop_record(&mut self, results: &Object, p: ::UpdateParameters) {
+        // Reloading all threads is only necessary for the thread list and for tracking the thread
+        // states in non-stop mode. Otherwise, the stopped thread is the current one.
+        let thread_id = results["thread-id"]
+            .as_str()
+            .and_then(|id| id.parse::<u64>().ok());
+        match thread_id {
+            Some(id) if !self.shows(TuiContainerType::Threads) && !p.gdb.mi.is_non_stop() => {
+                p.gdb.threads.set_current(id)
+            }
+            _ => {
+                if let Err(e) = p.gdb.refresh_threads() {
+                    warn!("Failed to update threads: {:?}", e);
+                }
+            }
+        }
+        if let JsonValue::Object(ref frame) = results["frame"] {
+            self.show_thread_frame(Some(frame), p);
+        } else {
+            self.show_thread_frame(None, p);
+        }
+    }
+
+    // Update all views that depend on the current thread (and its selected frame).
+    fn show_thread_frame(&mut self, frame: Option<&Object>, p: ::UpdateParameters) {
+        self.selected_frame = frame.cloned();
+        if let Some(frame) = frame {
+            self.src_view.show_frame(frame, p);
+        }
+        if self.shows(TuiContainerType::Backtrace) {
+            self.backtrace.update(frame, p);
+        }
+        if self.shows(TuiContainerType::Locals) {
+            self.locals.update(p);
+        }
+        self.expression_table.update_results(p);
+    }
+
+    // Reset all views that refer to the state of the previous gdb process and evaluate the
+    // watched expressions in the new one. (Pending evaluations are replaced by update_results.)
+    pub fn handle_gdb_respawn(&mut self, p: ::UpdateParameters) {
+        self.show_thread_frame(None, p);
+    }
+
+    // Enter post-mortem mode after gdb has loaded a core file: Report the signal that terminated
+    // the program, summarize all threads and show the faulting instruction.
+    pub fn show_core_dump(&mut self, p: ::UpdateParameters) {
+        match p.gdb.load_core_dump().map(|core| core.description()) {
+            Ok(description) => p.message_sink.send(description),
+            Err(e) => warn!("Failed to load core dump information: {:?}", e),
+        }
+        if self.shows(TuiContainerType::CoreSummary) {
+            self.core_summary.update(p);
+        }
+        match p.gdb.select_frame(0) {
+            Ok(frame) => {
+                self.src_view.prefer_assembly();
+                if self.shows(TuiContainerType::Registers) {
+                    self.registers.update(p);
+                }
+                self.show_thread_frame(Some(&frame), p);
+            }
+            Err(e) => p
+                .message_sink
+                .send(format!("Cannot show the faulting frame: {:?}", e)),
+        }
+    }
+
     pub fn add_out_of_band_record(&mut self, record: OutOfBandRecord, p: ::UpdateParameters) {
         match record {
             OutOfBandRecord::StreamRecord { kind: _, data } => {
+                let connected = p.gdb.remote.as_ref().map(|r| r.connected).unwrap_or(false);
+                if connected && is_remote_disconnect_message(&data) {
+                    p.gdb.handle_remote_disconnect();
+                    p.message_sink.send(
+                        "Lost connection to the remote target. Use '!reconnect' to connect again.",
+                    );
+                }
                 self.console.write_to_gdb_log(data);
             }
             OutOfBandRecord::AsyncRecord {
@@ -92,22 +316,102 @@ impl<'a> Tui<'a> {
         }
     }
 
+    pub fn selected_frame(&self) -> Option<&Object> {
+        self.selected_frame.as_ref()
+    }
+
+    pub fn add_result_record(&mut self, record: ResultRecord, p: ::UpdateParameters) {
+        if let Some((token, result)) = p.gdb.mi.complete(record) {
+            self.handle_async_result(token, result);
+        }
+    }
+
+    pub fn handle_global_stepping_keys(
+        &mut self,
+        input: Input,
+        p: ::UpdateParameters,
+    ) -> Option<Input> {
+        self.src_view.handle_global_stepping_keys(input, p)
+    }
+
+    pub fn handle_command_timeouts(&mut self, p: ::UpdateParameters) {
+        for token in p.gdb.mi.take_timed_out() {
+            self.handle_async_result(token, AsyncResult::TimedOut);
+        }
+    }
+
+    // Results of commands issued via execute_async are offered to all containers that issue
+    // asynchronous commands. Each of them only reacts to the tokens it is waiting for.
+    fn handle_async_result(&mut self, token: Token, result: AsyncResult) {
+        self.expression_table.handle_async_result(token, &result);
+    }
+
     pub fn add_pty_input(&mut self, input: &[u8]) {
         self.process_pty.add_byte_input(input);
     }
 
     pub fn update_after_event(&mut self, p: ::UpdateParameters) {
+        for message in p.gdb.take_messages() {
+            p.message_sink.send(message);
+        }
+        for event in p.tui_events.drain_events() {
+            match event {
+                TuiEvent::ShowLocation(src_pos, address) => {
+                    self.src_view.show_location(src_pos.as_ref(), address, p);
+                }
+                TuiEvent::FrameSelected(frame) => {
+                    self.selected_frame = Some(frame.clone());
+                    self.src_view.show_frame(&frame, p);
+                    self.backtrace.set_current_frame(&frame);
+                    if self.shows(TuiContainerType::Locals) {
+                        self.locals.update(p);
+                    }
+                    if self.shows(TuiContainerType::Registers) {
+                        self.registers.update(p);
+                    }
+                    self.expression_table.update_results(p);
+                }
+                TuiEvent::ThreadSelected(frame) => {
+                    if self.shows(TuiContainerType::Registers) {
+                        self.registers.update(p);
+                    }
+                    self.show_thread_frame(Some(&frame), p);
+                }
+                TuiEvent::ShowAttachPicker => {
+                    self.attach_picker = Some(AttachPicker::new(self.keymap));
+                }
+            }
+        }
+        if self
+            .attach_picker
+            .as_ref()
+            .map(|picker| picker.is_finished())
+            .unwrap_or(false)
+        {
+            self.attach_picker = None;
+        }
         self.src_view.update_after_event(p);
+        self.breakpoints.update_after_event(p);
+        self.threads.update_after_event(p);
+        self.libraries.update_after_event(p);
         self.console.update_after_event(p);
     }
 }
 
-#[derive(Clone, PartialEq, Debug)]
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
 pub enum TuiContainerType {
     SrcView,
     Console,
     ExpressionTable,
     Terminal,
+    Breakpoints,
+    Backtrace,
+    Threads,
+    Locals,
+    Registers,
+    Memory,
+    Libraries,
+    CoreSummary,
 }
 
 impl<'t> ContainerProvider for Tui<'t> {
@@ -119,6 +423,14 @@ impl<'t> ContainerProvider for Tui<'t> {
             &TuiContainerType::Console => &self.console,
             &TuiContainerType::ExpressionTable => &self.expression_table,
             &TuiContainerType::Terminal => &self.process_pty,
+            &TuiContainerType::Breakpoints => &self.breakpoints,
+            &TuiContainerType::Backtrace => &self.backtrace,
+            &TuiContainerType::Threads => &self.threads,
+            &TuiContainerType::Locals => &self.locals,
+            &TuiContainerType::Registers => &self.registers,
+            &TuiContainerType::Memory => &self.memory,
+            &TuiContainerType::Libraries => &self.libraries,
+            &TuiContainerType::CoreSummary => &self.core_summary,
         }
     }
     fn get_mut<'a, 'b: 'a>(
@@ -130,6 +442,14 @@ impl<'t> ContainerProvider for Tui<'t> {
             &TuiContainerType::Console => &mut self.console,
             &TuiContainerType::ExpressionTable => &mut self.expression_table,
             &TuiContainerType::Terminal => &mut self.process_pty,
+            &TuiContainerType::Breakpoints => &mut self.breakpoints,
+            &TuiContainerType::Backtrace => &mut self.backtrace,
+            &TuiContainerType::Threads => &mut self.threads,
+            &TuiContainerType::Locals => &mut self.locals,
+            &TuiContainerType::Registers => &mut self.registers,
+            &TuiContainerType::Memory => &mut self.memory,
+            &TuiContainerType::Libraries => &mut self.libraries,
+            &TuiContainerType::CoreSummary => &mut self.core_summary,
         }
     }
     const DEFAULT_CONTAINER: TuiContainerType = TuiContainerType::Console;